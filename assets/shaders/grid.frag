@@ -2,13 +2,71 @@
 
 precision mediump float;
 
+#define MAX_DIRECTIONAL_LIGHTS 4
+#define MAX_POINT_LIGHTS 4
+
+struct DirectionalLight {
+    vec3 direction;
+    vec3 color;
+};
+
+struct PointLight {
+    vec3 position;
+    vec3 color;
+    vec3 attenuation;
+};
+
 in VS_OUTPUT {
+    vec3 Position;
     vec3 Color;
+    vec3 Normal;
 } IN;
 
+uniform vec3 eye;
+uniform vec3 ambient;
+uniform float specular;
+uniform float shininess;
+
+uniform int directional_light_count;
+uniform DirectionalLight directional_lights[MAX_DIRECTIONAL_LIGHTS];
+uniform int point_light_count;
+uniform PointLight point_lights[MAX_POINT_LIGHTS];
+
 out vec4 Color;
 
+// Blinn-Phong diffuse and specular terms of a light reaching the surface from `to_light`
+vec3 shade(vec3 normal, vec3 to_eye, vec3 to_light, vec3 light_color)
+{
+    float diffuse = max(dot(normal, to_light), 0.0);
+    vec3 halfway = normalize(to_light + to_eye);
+    float highlight = diffuse > 0.0 ? pow(max(dot(normal, halfway), 0.0), shininess) : 0.0;
+
+    return light_color * (diffuse * IN.Color + specular * highlight);
+}
+
 void main()
 {
-    Color = vec4(IN.Color, 1.0f);
+    vec3 to_eye = normalize(eye - IN.Position);
+    vec3 normal = normalize(IN.Normal);
+    // Both sides of the surface are lit, as seen from the camera
+    if (dot(normal, to_eye) < 0.0) {
+        normal = -normal;
+    }
+
+    vec3 color = ambient * IN.Color;
+
+    for (int i = 0; i < directional_light_count; i++) {
+        color += shade(normal, to_eye, -directional_lights[i].direction, directional_lights[i].color);
+    }
+
+    for (int i = 0; i < point_light_count; i++) {
+        vec3 to_light = point_lights[i].position - IN.Position;
+        float d = length(to_light);
+        vec3 k = point_lights[i].attenuation;
+        float attenuation = 1.0 / (k.x + k.y * d + k.z * d * d);
+
+        color += attenuation * shade(normal, to_eye, to_light / d, point_lights[i].color);
+    }
+
+    Color = vec4(color, 1.0f);
 }
@@ -5,16 +5,37 @@ precision mediump float;
 layout (location = 0) in vec3 Position;
 layout (location = 1) in vec3 Color;
 layout (location = 2) in float Offset;
+layout (location = 3) in vec3 Normal;
+layout (location = 4) in float Colormapped;
 
+layout (location = 0) uniform mat4 model;
 layout (location = 1) uniform mat4 view;
 layout (location = 2) uniform mat4 projection;
 
+// Heights at the two ends of the colormap, which is sampled along the single row of the texture
+uniform vec2 value_range;
+uniform sampler2D colormap;
+
 out VS_OUTPUT {
+    vec3 Position;
     vec3 Color;
+    vec3 Normal;
 } OUT;
 
 void main()
 {
-    gl_Position = projection * view * vec4(Position.x, Position.y + Offset, Position.z, 1.0);
-    OUT.Color = Color;
+    vec4 position = model * vec4(Position.x, Position.y + Offset, Position.z, 1.0);
+
+    gl_Position = projection * view * position;
+    OUT.Position = position.xyz;
+    if (Colormapped > 0.5) {
+        // Texel centers span the colormap from the first to the last sample
+        float texels = float(textureSize(colormap, 0).x);
+        float t = clamp((Offset - value_range.x) / (value_range.y - value_range.x), 0.0, 1.0);
+        OUT.Color = texture(colormap, vec2((0.5 + t * (texels - 1.0)) / texels, 0.5)).rgb;
+    } else {
+        OUT.Color = Color;
+    }
+    // Keeps normals perpendicular to the surface under non-uniform scales
+    OUT.Normal = transpose(inverse(mat3(model))) * Normal;
 }
@@ -0,0 +1,17 @@
+#version 320 es
+
+precision mediump float;
+
+// Already in normalized device coordinates, so the legend stays put on the screen
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec3 Color;
+
+out VS_OUTPUT {
+    vec3 Color;
+} OUT;
+
+void main()
+{
+    gl_Position = vec4(Position, 1.0);
+    OUT.Color = Color;
+}
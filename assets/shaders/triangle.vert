@@ -5,6 +5,7 @@ precision mediump float;
 layout (location = 0) in vec3 Position;
 layout (location = 1) in vec3 Color;
 
+layout (location = 0) uniform mat4 model;
 layout (location = 1) uniform mat4 view;
 layout (location = 2) uniform mat4 projection;
 
@@ -14,6 +15,6 @@ out VS_OUTPUT {
 
 void main()
 {
-    gl_Position = projection * view * vec4(Position, 1.0);
+    gl_Position = projection * view * model * vec4(Position, 1.0);
     OUT.Color = Color;
-}
\ No newline at end of file
+}
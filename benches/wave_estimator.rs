@@ -0,0 +1,106 @@
+#[macro_use] extern crate criterion;
+extern crate maguey;
+
+use criterion::{BenchmarkId, Criterion};
+
+use maguey::wave_estimator::WaveEstimator;
+
+// The original HashMap backed estimator, kept here as a baseline to compare against
+mod legacy {
+    use std::collections::HashMap;
+
+    pub struct WaveEstimator {
+        granularity: i32,
+        alpha: f32,
+        vals : HashMap<(i32, i32), f32>,
+        speeds: HashMap<(i32, i32), f32>,
+    }
+
+    impl WaveEstimator {
+        pub fn new(granularity: i32, alpha: f32, start_func: impl Fn(f32, f32) -> f32) -> WaveEstimator {
+            let mut vals = HashMap::new();
+            let mut speeds= HashMap::new();
+
+            for x in -granularity..(granularity+1) {
+                for y in -granularity..(granularity+1) {
+                    let x_f = x as f32 / granularity as f32;
+                    let y_f = y as f32 / granularity as f32;
+
+                    let val: f32;
+                    if x <= -granularity || x >= granularity || y <= -granularity || y >= granularity {
+                        val = 0.0;
+                    } else {
+                        val = start_func(x_f, y_f);
+                    }
+
+                    vals.insert((x, y), val);
+                    speeds.insert((x, y), 0.0);
+                }
+            }
+
+            WaveEstimator { granularity, alpha, vals, speeds }
+        }
+
+        pub fn update(&mut self, dt: f32) {
+            let mut new_vals: HashMap<(i32, i32), f32> = HashMap::new();
+            let step  = 1.0 / self.granularity as f32;
+
+            for x in -self.granularity..(self.granularity+1) {
+                for y in -self.granularity..(self.granularity+1) {
+                    if x <= -self.granularity || x >= self.granularity || y <= -self.granularity || y >= self.granularity {
+                        new_vals.insert((x, y), 0.0);
+                    } else {
+                        let dfdx_right = (self.vals[&(x + 1, y)] - self.vals[&(x, y)]) / step;
+                        let dfdx_left = (self.vals[&(x, y)] - self.vals[&(x-1, y)]) / step;
+                        let d2fdx2 = (dfdx_right - dfdx_left) / step;
+
+                        let dfdy_up = (self.vals[&(x, y+1)] - self.vals[&(x, y)]) / step;
+                        let dfdy_down = (self.vals[&(x, y)] - self.vals[&(x, y-1)]) / step;
+                        let d2fdy2 = (dfdy_up - dfdy_down) / step;
+
+                        let d2fdt2 = self.alpha * (d2fdx2 + d2fdy2);
+
+                        *self.speeds
+                            .get_mut(&(x, y)).expect( "Speed does not contain all values") += d2fdt2 * dt;
+                        new_vals.insert((x, y), self.vals[&(x, y)] + self.speeds[&(x, y)] * dt);
+                    }
+                }
+            }
+
+            self.vals = new_vals;
+        }
+    }
+}
+
+const GRANULARITIES: [i32; 4] = [30, 100, 250, 500];
+
+fn start_func(x: f32, y: f32) -> f32 {
+    0.2 * f32::sin(2.0 * std::f32::consts::PI*(x+1.0)) * f32::sin(2.0 * std::f32::consts::PI*(y+1.0))
+}
+
+fn bench_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wave_estimator_update");
+    group.sample_size(10);
+
+    for granularity in GRANULARITIES.iter() {
+        group.bench_with_input(BenchmarkId::new("dense", granularity), granularity, |b, &g| {
+            let mut estimator = WaveEstimator::new(g, 5.0, start_func);
+            b.iter(|| estimator.update(0.0001).unwrap());
+        });
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(BenchmarkId::new("dense_parallel", granularity), granularity, |b, &g| {
+            let mut estimator = WaveEstimator::new(g, 5.0, start_func);
+            estimator.set_parallel(true);
+            b.iter(|| estimator.update(0.0001).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("hashmap", granularity), granularity, |b, &g| {
+            let mut estimator = legacy::WaveEstimator::new(g, 5.0, start_func);
+            b.iter(|| estimator.update(0.0001));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_update);
+criterion_main!(benches);
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+// Timing of the current frame, as handed to the game on every update and render
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameTime {
+    // Seconds to advance by. Updates always get the fixed tick length, renders the wall clock
+    // time since the previous frame.
+    pub dt: f32,
+    // Seconds of simulated time, i.e. the number of ticks run so far times the tick length
+    pub elapsed: f32,
+    // Index of the rendered frame, starting at 0
+    pub frame: u64,
+    // Fraction of a tick the wall clock is ahead of the last update, in [0, 1). Renders can use
+    // it to interpolate between the last two updates.
+    pub alpha: f32,
+}
+
+// Turns the irregular time between frames into a whole number of fixed-length ticks, carrying
+// the leftover time over to the next frame. The clock can be paused, stepped a tick at a time
+// and sped up or slowed down, which changes how many ticks run but never their length.
+pub struct FixedTimestep {
+    tick_dt: f32,
+    max_catch_up: u32,
+    accumulator: f32,
+    ticks: u64,
+    paused: bool,
+    pending_steps: u32,
+    time_scale: f32,
+}
+
+impl FixedTimestep {
+    // Panics unless `tick_rate` is a positive, finite number of ticks per second
+    pub fn new(tick_rate: f32) -> FixedTimestep {
+        FixedTimestep {
+            tick_dt: tick_dt(tick_rate),
+            max_catch_up: 8,
+            accumulator: 0.0,
+            ticks: 0,
+            paused: false,
+            pending_steps: 0,
+            time_scale: 1.0,
+        }
+    }
+
+    pub fn tick_rate(&self) -> f32 {
+        1.0 / self.tick_dt
+    }
+
+    // Panics on the same tick rates as `new`
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.tick_dt = tick_dt(tick_rate);
+    }
+
+    pub fn tick_dt(&self) -> f32 {
+        self.tick_dt
+    }
+
+    // Most ticks run in a single frame. When a frame takes longer than that many ticks, the
+    // time that is left over gets dropped and the simulation falls behind the wall clock
+    // instead of spending ever longer frames catching up.
+    pub fn set_max_catch_up(&mut self, max_catch_up: u32) {
+        self.max_catch_up = max_catch_up;
+    }
+
+    pub fn max_catch_up(&self) -> u32 {
+        self.max_catch_up
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.pending_steps = 0;
+    }
+
+    // Runs a single tick on the next frame, pausing the clock if it was running
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    // Simulated seconds per wall clock second
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale;
+    }
+
+    // Adds the wall clock time of a frame, returning how many ticks to run for it
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        if self.paused {
+            let ticks = self.pending_steps;
+            self.pending_steps = 0;
+            self.ticks += ticks as u64;
+
+            return ticks;
+        }
+
+        self.accumulator += frame_time.as_secs_f32() * self.time_scale;
+
+        // Fast forward gets as much room to catch up as normal speed does
+        let max_catch_up = self.max_catch_up * self.time_scale.ceil().max(1.0) as u32;
+        let due = (self.accumulator / self.tick_dt).floor() as u32;
+        let ticks = due.min(max_catch_up);
+
+        self.accumulator -= ticks as f32 * self.tick_dt;
+        if ticks < due {
+            self.accumulator = self.accumulator % self.tick_dt;
+        }
+        self.ticks += ticks as u64;
+
+        ticks
+    }
+
+    // Number of ticks run since the start
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    // Simulated time at the end of the last tick
+    pub fn elapsed(&self) -> f32 {
+        self.ticks as f32 * self.tick_dt
+    }
+
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.tick_dt).min(1.0)
+    }
+}
+
+fn tick_dt(tick_rate: f32) -> f32 {
+    assert!(
+        tick_rate > 0.0 && tick_rate.is_finite(),
+        "the tick rate must be a positive, finite number of ticks per second, not {}", tick_rate,
+    );
+
+    1.0 / tick_rate
+}
@@ -1,27 +1,85 @@
 use gl;
 use failure::err_msg;
 use std::path::Path;
+use std::time::Instant;
 use crate::{debug, render_gl};
+use crate::clock::{FixedTimestep, FrameTime};
 use crate::render_gl::resources::Resources;
 use nalgebra_glm as glm;
 use crate::render_gl::Viewport;
+use crate::render_gl::scene::Scene;
 
 pub trait BaseGame {
     fn load(&mut self, res: &Resources, gl: &gl::Gl) -> Result<(), failure::Error>;
-    fn update(&mut self, viewport: &mut Viewport) -> Result<(), failure::Error>;
-    fn render(&mut self, gl: &gl::Gl, viewport: &mut Viewport) -> Result<(), failure::Error>;
+    // Called once per tick of the fixed timestep clock, which may be several times or not at all
+    // per rendered frame
+    fn update(&mut self, time: &FrameTime, viewport: &mut Viewport) -> Result<(), failure::Error>;
+    fn render(&mut self, gl: &gl::Gl, time: &FrameTime, viewport: &mut Viewport) -> Result<(), failure::Error>;
+
+    // Objects drawn after `render` on every frame, with the camera of the viewport applied to them
+    fn scene(&mut self) -> Option<&mut Scene> {
+        None
+    }
+
+    // Called with the point of the y = 0 plane under a left click that was not a drag
+    fn on_click(&mut self, _point: glm::Vec3) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    fn on_key_down(&mut self, _keycode: sdl2::keyboard::Keycode) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    // Line of text shown in the window title, refreshed on every frame
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    // Moves through the recorded history by `offset` snapshots, returning whether there is one
+    fn scrub(&mut self, _offset: isize) -> Result<bool, failure::Error> {
+        Ok(false)
+    }
+
+    fn save_checkpoint(&mut self, _path: &Path) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    fn load_checkpoint(&mut self, _path: &Path) -> Result<(), failure::Error> {
+        Ok(())
+    }
 }
 
+// Mouse travel in pixels under which a left press and release count as a click, not a drag
+const CLICK_DRAG_THRESHOLD: i32 = 4;
+// Updates per second of the simulation clock, unless the game picks another rate
+const DEFAULT_TICK_RATE: f32 = 60.0;
+// Range the +/- keys move the time scale within, halving or doubling it on every press
+const MIN_TIME_SCALE: f32 = 1.0 / 16.0;
+const MAX_TIME_SCALE: f32 = 16.0;
+// Where F5 saves the game state and F9 loads it back from
+const CHECKPOINT_PATH: &str = "checkpoint.wave";
+
 
 pub struct GameExecutor<G: BaseGame> {
     game_impl: G,
     screen_dims: (u32, u32),
+    clock: FixedTimestep,
 }
 
 
 impl<G: BaseGame> GameExecutor<G> {
     pub fn new(game_impl: G, screen_dims: (u32, u32)) -> GameExecutor<G> {
-        return GameExecutor{ game_impl, screen_dims }
+        return GameExecutor{ game_impl, screen_dims, clock: FixedTimestep::new(DEFAULT_TICK_RATE) }
+    }
+
+    // Number of times per second `BaseGame::update` gets called, independently of the frame rate
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.clock.set_tick_rate(tick_rate);
+    }
+
+    // Most updates run back to back when rendering falls behind the clock
+    pub fn set_max_catch_up(&mut self, max_catch_up: u32) {
+        self.clock.set_max_catch_up(max_catch_up);
     }
 
     pub fn run(&mut self) {
@@ -30,6 +88,22 @@ impl<G: BaseGame> GameExecutor<G> {
         }
     }
 
+    // Window title with the game's status and the state of the simulation clock
+    fn title(&self) -> String {
+        let mut title = "Game".to_string();
+
+        if let Some(status) = self.game_impl.status() {
+            title += &format!(" - {}", status);
+        }
+        if self.clock.is_paused() {
+            title += " [paused]";
+        } else if self.clock.time_scale() != 1.0 {
+            title += &format!(" [x{}]", self.clock.time_scale());
+        }
+
+        title
+    }
+
     fn execute(&mut self) -> Result<(), failure::Error> {
         let res = Resources::from_relative_exe_path(Path::new("assets-07"))?;
 
@@ -42,11 +116,13 @@ impl<G: BaseGame> GameExecutor<G> {
         );
 
         let mut dragging = false;
+        let mut drag_distance = 0;
 
         gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
         gl_attr.set_context_version(4, 1);
+        gl_attr.set_depth_size(24);
 
-        let window = video_subsystem
+        let mut window = video_subsystem
             .window("Game", self.screen_dims.0, self.screen_dims.1)
             .opengl()
             .resizable()
@@ -66,12 +142,16 @@ impl<G: BaseGame> GameExecutor<G> {
             glm::vec3(1.5, 1.5, 1.5),
         );
 
-        self.game_impl.load(&res, &gl);
+        self.game_impl.load(&res, &gl)?;
 
         viewport.set_used(&gl);
         color_buffer.set_used(&gl);
+        // Surfaces hide whatever is behind them
+        unsafe { gl.Enable(gl::DEPTH_TEST) };
 
         let mut event_pump = sdl.event_pump().map_err(err_msg)?;
+        let mut last_frame = Instant::now();
+        let mut frame = 0;
         'main: loop {
             for event in event_pump.poll_iter() {
                 match event {
@@ -89,12 +169,20 @@ impl<G: BaseGame> GameExecutor<G> {
                         ..
                     } => {
                         dragging = true;
+                        drag_distance = 0;
                     },
                     sdl2::event::Event::MouseButtonUp {
                         mouse_btn: sdl2::mouse::MouseButton::Left,
+                        x,
+                        y,
                         ..
                     } => {
                         dragging = false;
+                        if drag_distance < CLICK_DRAG_THRESHOLD {
+                            if let Some(point) = viewport.pick_plane(x, y, 0.0) {
+                                self.game_impl.on_click(point)?;
+                            }
+                        }
                     }
                     sdl2::event::Event::MouseButtonDown {
                         mouse_btn,
@@ -114,6 +202,7 @@ impl<G: BaseGame> GameExecutor<G> {
                         ..
                     } => {
                         if dragging {
+                            drag_distance += xrel.abs() + yrel.abs();
                             viewport.rotate_by(xrel as f32, yrel as f32, 5.0);
                         }
                     },
@@ -128,17 +217,130 @@ impl<G: BaseGame> GameExecutor<G> {
                         keycode: Some(sdl2::keyboard::Keycode::Escape),
                         ..
                     } => break 'main,
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Space),
+                        repeat: false,
+                        ..
+                    } => {
+                        let paused = !self.clock.is_paused();
+                        self.clock.set_paused(paused);
+                    },
+                    // Holding the key down keeps stepping
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Period),
+                        ..
+                    } => self.clock.step(),
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Plus),
+                        ..
+                    } |
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Equals),
+                        ..
+                    } |
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::KpPlus),
+                        ..
+                    } => {
+                        let time_scale = (self.clock.time_scale() * 2.0).min(MAX_TIME_SCALE);
+                        self.clock.set_time_scale(time_scale);
+                    },
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Minus),
+                        ..
+                    } |
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::KpMinus),
+                        ..
+                    } => {
+                        let time_scale = (self.clock.time_scale() / 2.0).max(MIN_TIME_SCALE);
+                        self.clock.set_time_scale(time_scale);
+                    },
+                    // Scrubbing through the history pauses the clock, so the restored state stays
+                    // on screen until the simulation is resumed or stepped
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Left),
+                        ..
+                    } => {
+                        if self.game_impl.scrub(-1)? {
+                            self.clock.set_paused(true);
+                        }
+                    },
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Right),
+                        ..
+                    } => {
+                        if self.game_impl.scrub(1)? {
+                            self.clock.set_paused(true);
+                        }
+                    },
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::F5),
+                        repeat: false,
+                        ..
+                    } => {
+                        // A failed save or load is reported without ending the game
+                        match self.game_impl.save_checkpoint(Path::new(CHECKPOINT_PATH)) {
+                            Ok(()) => println!("Saved checkpoint to {}", CHECKPOINT_PATH),
+                            Err(e) => println!("{}", debug::failure_to_string(e)),
+                        }
+                    },
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::F9),
+                        repeat: false,
+                        ..
+                    } => {
+                        match self.game_impl.load_checkpoint(Path::new(CHECKPOINT_PATH)) {
+                            Ok(()) => println!("Loaded checkpoint from {}", CHECKPOINT_PATH),
+                            Err(e) => println!("{}", debug::failure_to_string(e)),
+                        }
+                    },
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(keycode),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.game_impl.on_key_down(keycode)?;
+                    },
                     _ => {},
                 }
             }
 
-            self.game_impl.update(&mut viewport);
+            let now = Instant::now();
+            let frame_time = now - last_frame;
+            last_frame = now;
+
+            let ticks = self.clock.advance(frame_time);
+            let tick_dt = self.clock.tick_dt();
+            for tick in (self.clock.ticks() - ticks as u64)..self.clock.ticks() {
+                let time = FrameTime { dt: tick_dt, elapsed: tick as f32 * tick_dt, frame, alpha: 0.0 };
+                // A failed step, e.g. one rejected as unstable, is reported and pauses the clock
+                // rather than ending the game, so the settings can be changed and the simulation
+                // resumed
+                if let Err(e) = self.game_impl.update(&time, &mut viewport) {
+                    println!("{}", debug::failure_to_string(e));
+                    self.clock.set_paused(true);
+                    break;
+                }
+            }
+
+            window.set_title(&self.title())?;
 
             color_buffer.clear(&gl);
 
-            self.game_impl.render(&gl, &mut viewport);
+            let time = FrameTime {
+                dt: frame_time.as_secs_f32(),
+                elapsed: self.clock.elapsed(),
+                frame,
+                alpha: self.clock.alpha(),
+            };
+            self.game_impl.render(&gl, &time, &mut viewport)?;
+            if let Some(scene) = self.game_impl.scene() {
+                scene.render(&gl, &mut viewport)?;
+            }
 
             window.gl_swap_window();
+            frame += 1;
         }
 
         Ok(())
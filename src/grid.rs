@@ -1,28 +1,104 @@
 use gl;
 use failure;
+use nalgebra_glm as glm;
 
 use crate::render_gl::{self, data, buffer};
+use crate::render_gl::colormap::{Colormap, ColorRange};
 use crate::render_gl::resources::Resources;
+use crate::render_gl::scene::Renderable;
+use crate::render_gl::texture::{Texture2D, TextureFormat};
+use crate::render_gl::transform::Transform;
+use crate::render_gl::uniform::{self, Uniform, UniformFMat4};
+use crate::heightmap::Heightmap;
+use crate::mesh::Mesh;
+use crate::wave_estimator::Domain;
+
+// Color of the whole surface when no colormap is set
+const SURFACE_COLOR: (f32, f32, f32) = (1.0, 0.0, 0.0);
+// Color of the points where the surface is masked out by an obstacle
+const MASK_COLOR: (f32, f32, f32) = (0.6, 0.6, 0.6);
+// Samples of the colormap in the texture the vertex shader looks colors up in
+const COLORMAP_TEXELS: u32 = 256;
+// Texture unit the colormap is bound to while drawing
+const COLORMAP_UNIT: u32 = 0;
+
+// How the surface gets drawn: filled triangles, the edges between neighbouring lattice points, or
+// the lattice points alone
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DisplayMode {
+    Solid,
+    Wireframe,
+    Points,
+}
+
+impl DisplayMode {
+    // Mode after this one, going back to the first after the last
+    pub fn next(self) -> DisplayMode {
+        match self {
+            DisplayMode::Solid => DisplayMode::Wireframe,
+            DisplayMode::Wireframe => DisplayMode::Points,
+            DisplayMode::Points => DisplayMode::Solid,
+        }
+    }
+}
+
+// The attributes of every vertex are split over three buffers by how often they change: the
+// positions never do, the colors when the mask or colormap does, and the heights on every frame.
+// Colors by height get looked up from the heights in the vertex shader, so they do not have to
+// be uploaded on every frame.
 
 #[derive(VertexAttribPointers)]
 #[derive(Copy, Clone, Debug)]
 #[repr(C, packed)]
-struct Vertex {
+struct PositionVertex {
     #[location = 0]
     pos: data::f32_f32_f32,
+}
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct ColorVertex {
     #[location = 1]
     clr: data::f32_f32_f32,
+    // 1 where the color comes from the colormap instead of `clr`, 0 elsewhere
+    #[location = 4]
+    colormapped: data::one_f32,
+}
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct HeightVertex {
     #[location = 2]
     offset: data::one_f32,
+    #[location = 3]
+    normal: data::f32_f32_f32,
 }
 
 pub struct Grid {
     program: render_gl::Program,
-    vbo: buffer::ArrayBuffer,
+    _position_vbo: buffer::ArrayBuffer,
+    color_vbo: buffer::ArrayBuffer,
+    height_vbo: buffer::ArrayBuffer,
+    _ebo: buffer::ElementArrayBuffer,
     vao: buffer::VertexArray,
-    vertices: Vec<Vertex>,
-    _scale: f32,
-    num_steps: i32,
+    positions: Vec<PositionVertex>,
+    colors: Vec<ColorVertex>,
+    heights: Vec<HeightVertex>,
+    // The element buffer holds the indices of the triangles followed by those of the lines
+    triangle_indices: usize,
+    line_indices: usize,
+    display_mode: DisplayMode,
+    masked: Vec<bool>,
+    colormap: Option<Colormap>,
+    colormap_texture: Texture2D,
+    color_range: ColorRange,
+    // Heights mapped to the two ends of the colormap on the last update
+    value_range: (f32, f32),
+    transform: Transform,
+    model_uniform: UniformFMat4,
+    domain: Domain,
 }
 
 impl Grid {
@@ -31,90 +107,383 @@ impl Grid {
         gl: &gl::Gl,
         scale: f32,
         num_steps: i32
+    ) -> Result<Grid, failure::Error> {
+        Grid::new_with_domain(res, gl, scale, Domain::square(num_steps))
+    }
+
+    // Grid over a rectangular domain, with a vertex on every one of its lattice points
+    pub fn new_with_domain(
+        res: &Resources,
+        gl: &gl::Gl,
+        scale: f32,
+        domain: Domain,
+    ) -> Result<Grid, failure::Error> {
+        Grid::build(res, gl, scale, domain, |_x, _y| { 0.0_f32 })
+    }
+
+    // Grid over the domain of the heightmap, starting out displaced by it
+    pub fn new_with_heightmap(
+        res: &Resources,
+        gl: &gl::Gl,
+        scale: f32,
+        heightmap: &Heightmap,
+    ) -> Result<Grid, failure::Error> {
+        Grid::build(res, gl, scale, heightmap.domain(), |x, y| heightmap.height(x, y))
+    }
+
+    fn build(
+        res: &Resources,
+        gl: &gl::Gl,
+        scale: f32,
+        domain: Domain,
+        f: impl Fn(f32, f32) -> f32,
     ) -> Result<Grid, failure::Error> {
         // Setup shader program
         let program = render_gl::Program::from_res(gl, res, "shaders/grid")?;
 
-        let vertices = Grid::generate_vertices(
-            scale, num_steps, |_x, _y| { 0.0_f32 },
-        );
+        let (positions, colors, mut heights) = Grid::generate_vertices(domain, f);
+        Grid::update_normals(domain, &positions, &mut heights);
+
+        let triangles = Grid::generate_triangle_indices(domain);
+        let lines = Grid::generate_line_indices(domain);
+        let indices: Vec<u32> = triangles.iter().chain(lines.iter()).cloned().collect();
 
-        let vbo = buffer::ArrayBuffer::new(&gl);
-        vbo.bind();
-        vbo.dynamic_draw_data(&vertices);
-        vbo.unbind();
+        let position_vbo = buffer::ArrayBuffer::new(&gl);
+        position_vbo.bind();
+        position_vbo.static_draw_data(&positions);
+        position_vbo.unbind();
 
-        // Setup vertex array buffer
+        let color_vbo = buffer::ArrayBuffer::new(&gl);
+        color_vbo.bind();
+        color_vbo.dynamic_draw_data(&colors);
+        color_vbo.unbind();
+
+        let height_vbo = buffer::ArrayBuffer::new(&gl);
+        height_vbo.bind();
+        height_vbo.stream_draw_data(&heights);
+        height_vbo.unbind();
+
+        // Setup vertex array buffer. The element buffer binding is part of the vertex array
+        // state, so it has to stay bound until the vertex array is unbound.
         let vao = buffer::VertexArray::new(gl);
+        let ebo = buffer::ElementArrayBuffer::new(&gl);
 
         vao.bind();
-        vbo.bind();
-        Vertex::vertex_attrib_pointers(gl);
-        vbo.unbind();
+        position_vbo.bind();
+        PositionVertex::vertex_attrib_pointers(gl);
+        color_vbo.bind();
+        ColorVertex::vertex_attrib_pointers(gl);
+        height_vbo.bind();
+        HeightVertex::vertex_attrib_pointers(gl);
+        ebo.bind();
+        ebo.static_draw_data(&indices);
         vao.unbind();
+        height_vbo.unbind();
+        ebo.unbind();
 
-        Ok(Grid{program, vbo, vao, vertices, _scale: scale, num_steps})
+        Ok(Grid{
+            program,
+            _position_vbo: position_vbo,
+            color_vbo,
+            height_vbo,
+            _ebo: ebo,
+            vao,
+            positions,
+            colors,
+            heights,
+            triangle_indices: triangles.len(),
+            line_indices: lines.len(),
+            display_mode: DisplayMode::Solid,
+            masked: vec![false; domain.len()],
+            colormap: None,
+            colormap_texture: Texture2D::new(gl, COLORMAP_TEXELS, 1, TextureFormat::Rgba32F),
+            color_range: ColorRange::Symmetric,
+            value_range: (-0.5, 0.5),
+            // The lattice spans the domain, so the scale only stretches it horizontally
+            transform: Transform::from_scale(glm::vec3(scale, 1.0, scale)),
+            model_uniform: UniformFMat4::new_with_loc(gl, "model", glm::Mat4::identity(), 0),
+            domain,
+        })
     }
 
     pub fn get_program_id(&self) -> gl::types::GLuint {
         self.program.id()
     }
 
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    // Places the grid in the world without touching its vertices
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    pub fn display_mode(&self) -> DisplayMode {
+        self.display_mode
+    }
+
+    pub fn set_display_mode(&mut self, display_mode: DisplayMode) {
+        self.display_mode = display_mode;
+    }
+
+    // Colors the surface by height through `colormap`, or all in SURFACE_COLOR when given `None`
+    pub fn set_colormap(&mut self, colormap: Option<Colormap>) {
+        if let Some(colormap) = colormap.as_ref() {
+            let texels: Vec<f32> = (0..COLORMAP_TEXELS)
+                .flat_map(|i| {
+                    let color = colormap.color(i as f32 / (COLORMAP_TEXELS - 1) as f32);
+                    vec![color.x, color.y, color.z, 1.0]
+                })
+                .collect();
+            self.colormap_texture.set_data(&texels);
+        }
+
+        self.colormap = colormap;
+        self.update_value_range();
+        self.update_colors();
+    }
+
+    pub fn colormap(&self) -> Option<&Colormap> {
+        self.colormap.as_ref()
+    }
+
+    // Takes effect on the next update of the vertices
+    pub fn set_color_range(&mut self, color_range: ColorRange) {
+        self.color_range = color_range;
+    }
+
+    pub fn color_range(&self) -> ColorRange {
+        self.color_range
+    }
+
+    // Heights currently shown with the lowest and highest colors of the colormap
+    pub fn value_range(&self) -> (f32, f32) {
+        self.value_range
+    }
+
+    // Sets the height of every vertex from `f`, called with the domain coordinates of its point
     pub fn update_vertices(&mut self, f: impl Fn(f32, f32) -> f32) {
-        let mut idx = 0;
-        for x in -self.num_steps..(self.num_steps+1) {
-            for y in -self.num_steps..(self.num_steps+1) {
-                let x_f = x as f32 / (self.num_steps as f32);
-                let y_f = y as f32 / (self.num_steps as f32);
-
-                self.vertices[idx].offset = f(x_f, y_f).into();
-                idx += 1;
-            }
+        for (vertex, (_, _, (x_f, y_f))) in self.heights.iter_mut().zip(self.domain.points()) {
+            vertex.offset = f(x_f, y_f).into();
         }
+        Grid::update_normals(self.domain, &self.positions, &mut self.heights);
+        self.upload_heights();
+        self.update_value_range();
+    }
 
-        self.vbo.bind();
-        self.vbo.dynamic_draw_data(&self.vertices);
-        self.vbo.unbind();
+    // Colors the vertices where `masked` is true as obstacles, and the rest as the surface
+    pub fn set_mask(&mut self, masked: impl Fn(f32, f32) -> bool) {
+        for (is_masked, (_, _, (x_f, y_f))) in self.masked.iter_mut().zip(self.domain.points()) {
+            *is_masked = masked(x_f, y_f);
+        }
+        self.update_value_range();
+        self.update_colors();
     }
 
-    pub fn render(&mut self, gl: &gl::Gl) {
-        self.program.set_used();
-        self.vao.bind();
+    // Surface as last updated, displaced, colored and placed in the world by `model` on top of
+    // its own transform, the same way `render` draws it. Pass the world matrix of the scene node
+    // holding the grid, or the identity for a grid outside of any scene.
+    pub fn mesh(&self, model: &glm::Mat4) -> Mesh {
+        let triangles = Grid::generate_triangle_indices(self.domain)
+            .chunks(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+        let matrix = model * self.transform.matrix();
+        // Normals scale by the inverse of the scale to stay perpendicular to the surface
+        let normal_matrix = glm::transpose(&glm::inverse(&glm::mat4_to_mat3(&matrix)));
 
-        unsafe {
-            gl.DrawArrays(
-                gl::POINTS,  // mode
-                0,  // starting index in the enabled arrays
-                self.vertices.len() as gl::types::GLsizei,  // number of indices to be rendered
-            );
+        Mesh {
+            positions: self.positions.iter().zip(self.heights.iter())
+                .map(|(p, h)| {
+                    let position = matrix * glm::vec4(p.pos.d0, p.pos.d1 + h.offset.d0, p.pos.d2, 1.0);
+                    position.xyz() / position.w
+                })
+                .collect(),
+            normals: self.heights.iter()
+                .map(|h| glm::normalize(&(normal_matrix * glm::vec3(h.normal.d0, h.normal.d1, h.normal.d2))))
+                .collect(),
+            colors: self.colors.iter().zip(self.heights.iter())
+                .map(|(c, h)| match self.colormap.as_ref() {
+                    Some(colormap) if c.colormapped.d0 > 0.5 => {
+                        let (min, max) = self.value_range;
+                        colormap.color((h.offset.d0 - min) / (max - min))
+                    },
+                    _ => glm::vec3(c.clr.d0, c.clr.d1, c.clr.d2),
+                })
+                .collect(),
+            triangles,
         }
     }
 
+    // The heights get rewritten whole on every frame, so they go to fresh storage that a draw
+    // still reading the previous frame's heights does not have to finish with first
+    fn upload_heights(&mut self) {
+        self.height_vbo.bind();
+        self.height_vbo.orphan::<HeightVertex>(self.heights.len(), buffer::Usage::Stream);
+
+        match self.height_vbo.map_range::<HeightVertex>(0, self.heights.len()) {
+            Some(mut mapped) => mapped.write(0, &self.heights),
+            None => self.height_vbo.sub_data(0, &self.heights),
+        }
+
+        self.height_vbo.unbind();
+    }
+
+    // Obstacles are left out of the range of heights, as they always sit at 0
+    fn update_value_range(&mut self) {
+        if self.colormap.is_none() {
+            return;
+        }
+
+        let heights = self.heights.iter().zip(self.masked.iter())
+            .filter(|(_, &masked)| !masked)
+            .map(|(vertex, _)| vertex.offset.d0);
+        self.value_range = self.color_range.resolve(heights);
+    }
+
+    // Only needed when the mask or the colormap changes, as the colors by height are left to the
+    // vertex shader
+    fn update_colors(&mut self) {
+        let colormapped = self.colormap.is_some();
+
+        for (vertex, &masked) in self.colors.iter_mut().zip(self.masked.iter()) {
+            vertex.clr = if masked { MASK_COLOR } else { SURFACE_COLOR }.into();
+            vertex.colormapped = if colormapped && !masked { 1.0 } else { 0.0 }.into();
+        }
+
+        self.color_vbo.bind();
+        self.color_vbo.sub_data(0, &self.colors);
+        self.color_vbo.unbind();
+    }
+
     fn generate_vertices(
-        scale: f32,
-        num_steps: i32,
+        domain: Domain,
         f: impl Fn(f32, f32) -> f32
-    ) -> Vec<Vertex> {
+    ) -> (Vec<PositionVertex>, Vec<ColorVertex>, Vec<HeightVertex>) {
         // Setup vertices
 
-        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut positions = Vec::with_capacity(domain.len());
+        let mut colors = Vec::with_capacity(domain.len());
+        let mut heights = Vec::with_capacity(domain.len());
+
+        for (_, _, (x_f, y_f)) in domain.points() {
+            positions.push(PositionVertex { pos: (x_f, 0.0, y_f).into() });
+            colors.push(ColorVertex { clr: SURFACE_COLOR.into(), colormapped: 0.0.into() });
+            heights.push(HeightVertex { offset: f(x_f, y_f).into(), normal: (0.0, 1.0, 0.0).into() });
+        }
+
+        (positions, colors, heights)
+    }
+
+    // Points every normal away from the surface, using the central difference between the
+    // displaced neighbours of each vertex, or a one-sided one on the edges
+    fn update_normals(domain: Domain, positions: &[PositionVertex], heights: &mut [HeightVertex]) {
+        let (rows, cols) = (domain.rows(), domain.cols());
+        let mut normals = Vec::with_capacity(heights.len());
 
-        for x in -num_steps..(num_steps+1) {
-            for y in -num_steps..(num_steps+1) {
-                let x_f = x as f32 / (num_steps as f32);
-                let y_f = y as f32 / (num_steps as f32);
+        for row in 0..rows {
+            for col in 0..cols {
+                let at = |r: usize, c: usize| {
+                    let (p, h) = (&positions[r * cols + c], &heights[r * cols + c]);
+                    glm::vec3(p.pos.d0, p.pos.d1 + h.offset.d0, p.pos.d2)
+                };
 
-                let scaled_x = x_f * scale as f32;
-                let scaled_y = y_f * scale as f32;
+                let along_x = at((row + 1).min(rows - 1), col) - at(row.saturating_sub(1), col);
+                let along_z = at(row, (col + 1).min(cols - 1)) - at(row, col.saturating_sub(1));
 
-                vertices.push(Vertex {
-                    pos: (scaled_x, 0.0, scaled_y).into(),
-                    clr: (1.0, 0.0, 0.0).into(),
-                    offset: f(x_f, y_f).into(),
-                });
+                normals.push(glm::normalize(&along_z.cross(&along_x)));
+            }
+        }
+
+        for (vertex, normal) in heights.iter_mut().zip(normals) {
+            vertex.normal = normal.into();
+        }
+    }
+
+    // Two triangles for every cell between four neighbouring lattice points, indexed the same way
+    // as `Domain::idx` and wound counterclockwise seen from above
+    fn generate_triangle_indices(domain: Domain) -> Vec<u32> {
+        let (rows, cols) = (domain.rows(), domain.cols());
+        let mut indices = Vec::with_capacity(6 * (rows - 1) * (cols - 1));
+
+        for row in 0..rows - 1 {
+            for col in 0..cols - 1 {
+                let i = (row * cols + col) as u32;
+                let (below, right) = (i + cols as u32, i + 1);
+
+                indices.extend_from_slice(&[i, right, below, right, below + 1, below]);
+            }
+        }
+
+        indices
+    }
+
+    // A line from every lattice point to its next neighbour along each axis
+    fn generate_line_indices(domain: Domain) -> Vec<u32> {
+        let (rows, cols) = (domain.rows(), domain.cols());
+        let mut indices = Vec::with_capacity(2 * (2 * rows * cols - rows - cols));
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let i = (row * cols + col) as u32;
+
+                if row < rows - 1 {
+                    indices.extend_from_slice(&[i, i + cols as u32]);
+                }
+                if col < cols - 1 {
+                    indices.extend_from_slice(&[i, i + 1]);
+                }
+            }
+        }
+
+        indices
+    }
+}
+
+impl Renderable for Grid {
+    fn camera_program(&self) -> Option<gl::types::GLuint> {
+        Some(self.program.id())
+    }
+
+    fn render(&mut self, gl: &gl::Gl, model: &glm::Mat4) -> Result<(), failure::Error> {
+        self.program.set_used();
+        self.model_uniform.update(&(model * self.transform.matrix()));
+        self.model_uniform.apply_uniform(self.program.id())?;
+        unsafe {
+            let (min, max) = self.value_range;
+            gl.Uniform2f(uniform::location(gl, self.program.id(), "value_range")?, min, max);
+            gl.Uniform1i(uniform::location(gl, self.program.id(), "colormap")?, COLORMAP_UNIT as i32);
+        }
+        self.colormap_texture.bind(COLORMAP_UNIT);
+        self.vao.bind();
+
+        unsafe {
+            match self.display_mode {
+                DisplayMode::Solid => gl.DrawElements(
+                    gl::TRIANGLES,  // mode
+                    self.triangle_indices as gl::types::GLsizei,  // number of indices to be rendered
+                    gl::UNSIGNED_INT,  // type of the indices
+                    std::ptr::null(),  // offset of the first index in the element buffer
+                ),
+                DisplayMode::Wireframe => gl.DrawElements(
+                    gl::LINES,
+                    self.line_indices as gl::types::GLsizei,
+                    gl::UNSIGNED_INT,
+                    (self.triangle_indices * std::mem::size_of::<u32>()) as *const gl::types::GLvoid,
+                ),
+                DisplayMode::Points => gl.DrawArrays(
+                    gl::POINTS,  // mode
+                    0,  // starting index in the enabled arrays
+                    self.positions.len() as gl::types::GLsizei,  // number of indices to be rendered
+                ),
             }
         }
 
-        vertices
+        Ok(())
     }
 }
@@ -0,0 +1,52 @@
+use crate::render_gl::image::{self, GrayImage};
+use crate::render_gl::resources::Resources;
+use crate::wave_estimator::Domain;
+
+// Heights on every lattice point of a domain, taken from the brightness of an image stretched
+// over it: x runs along the image from left to right and y from top to bottom. Black gives a
+// height of 0 and white one of `height_scale`.
+pub struct Heightmap {
+    domain: Domain,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    pub fn from_res(
+        res: &Resources,
+        name: &str,
+        domain: Domain,
+        height_scale: f32,
+    ) -> Result<Heightmap, image::Error> {
+        let image = GrayImage::from_res(res, name)?;
+
+        Ok(Heightmap::from_image(&image, domain, height_scale))
+    }
+
+    // Every lattice point takes the mean brightness of the part of the image its cell covers, so
+    // images larger than the lattice get averaged down rather than skipped over, and smaller
+    // ones get interpolated
+    pub fn from_image(image: &GrayImage, domain: Domain, height_scale: f32) -> Heightmap {
+        let (du, dv) = (domain.dx() / domain.width, domain.dy() / domain.height);
+        let mut heights = vec![0.0; domain.len()];
+
+        for (x, y, (x_f, y_f)) in domain.points() {
+            let (u, v) = (x_f / domain.width + 0.5, y_f / domain.height + 0.5);
+            heights[domain.idx(x, y)] = height_scale * image.sample_box(u, v, du, dv);
+        }
+
+        Heightmap { domain, heights }
+    }
+
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
+
+    // Height of the lattice point closest to (x, y), or 0 outside of the domain. Meant to be
+    // passed as the function of position that grids and estimators get built from.
+    pub fn height(&self, x: f32, y: f32) -> f32 {
+        match self.domain.closest_point(x, y) {
+            Some((x, y)) => self.heights[self.domain.idx(x, y)],
+            None => 0.0,
+        }
+    }
+}
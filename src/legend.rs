@@ -0,0 +1,104 @@
+use gl;
+use failure;
+use nalgebra_glm as glm;
+
+use crate::render_gl::{self, data, buffer};
+use crate::render_gl::colormap::Colormap;
+use crate::render_gl::resources::Resources;
+use crate::render_gl::scene::Renderable;
+
+// Corners of the color bar in normalized device coordinates, along the right edge of the window
+const LEFT: f32 = 0.88;
+const RIGHT: f32 = 0.92;
+const BOTTOM: f32 = -0.8;
+const TOP: f32 = 0.8;
+// The bar sits in front of everything else, at the near end of the depth range
+const DEPTH: f32 = -1.0;
+// Number of bands the colormap gets sampled into
+const SEGMENTS: usize = 64;
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32,
+    #[location = 1]
+    clr: data::f32_f32_f32,
+}
+
+// Vertical bar showing a colormap, from the lowest value at the bottom to the highest at the top
+pub struct Legend {
+    program: render_gl::Program,
+    vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+    vertices: Vec<Vertex>,
+}
+
+impl Legend {
+    pub fn new(res: &Resources, gl: &gl::Gl, colormap: &Colormap) -> Result<Legend, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/legend")?;
+        let vertices = Legend::generate_vertices(colormap);
+
+        let vbo = buffer::ArrayBuffer::new(&gl);
+        vbo.bind();
+        vbo.dynamic_draw_data(&vertices);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Legend{program, vbo, vao, vertices})
+    }
+
+    pub fn set_colormap(&mut self, colormap: &Colormap) {
+        self.vertices = Legend::generate_vertices(colormap);
+
+        self.vbo.bind();
+        self.vbo.dynamic_draw_data(&self.vertices);
+        self.vbo.unbind();
+    }
+
+    // A pair of vertices across the bar for every band edge, making up a triangle strip
+    fn generate_vertices(colormap: &Colormap) -> Vec<Vertex> {
+        let mut vertices = Vec::with_capacity(2 * (SEGMENTS + 1));
+
+        for i in 0..=SEGMENTS {
+            let t = i as f32 / SEGMENTS as f32;
+            let y = BOTTOM + t * (TOP - BOTTOM);
+            let color = colormap.color(t);
+
+            vertices.push(Vertex { pos: (LEFT, y, DEPTH).into(), clr: color.into() });
+            vertices.push(Vertex { pos: (RIGHT, y, DEPTH).into(), clr: color.into() });
+        }
+
+        vertices
+    }
+}
+
+impl Renderable for Legend {
+    fn camera_program(&self) -> Option<gl::types::GLuint> {
+        None
+    }
+
+    // Drawn in screen space, so placing it in the world has no effect
+    fn render(&mut self, gl: &gl::Gl, _model: &glm::Mat4) -> Result<(), failure::Error> {
+        self.program.set_used();
+        self.vao.bind();
+
+        unsafe {
+            gl.DrawArrays(
+                gl::TRIANGLE_STRIP,  // mode
+                0,  // starting index in the enabled arrays
+                self.vertices.len() as gl::types::GLsizei,  // number of indices to be rendered
+            );
+        }
+
+        Ok(())
+    }
+}
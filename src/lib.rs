@@ -0,0 +1,19 @@
+#[macro_use] extern crate failure;
+#[macro_use] extern crate render_gl_derive;
+
+extern crate gl;
+extern crate sdl2;
+extern crate nalgebra_glm;
+
+pub mod render_gl;
+pub mod clock;
+pub mod triangle;
+pub mod game;
+pub mod grid;
+pub mod heightmap;
+pub mod legend;
+pub mod mesh;
+pub mod noise;
+pub mod debug;
+pub mod simulation;
+pub mod wave_estimator;
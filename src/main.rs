@@ -1,25 +1,52 @@
 #[macro_use] extern crate failure;
-#[macro_use] extern crate render_gl_derive;
 #[macro_use] extern crate maplit;
 #[macro_use] extern crate lazy_static;
 
 extern crate gl;
 extern crate sdl2;
 extern crate nalgebra_glm;
+extern crate maguey;
 
+use std::fs::File;
+use std::path::Path;
 use std::time::SystemTime;
 
-use crate::render_gl::resources::Resources;
-use crate::render_gl::Viewport;
-use crate::wave_estimator::WaveEstimator;
-use crate::grid::Grid;
+use maguey::{debug, game};
+use maguey::clock::FrameTime;
+use maguey::render_gl::colormap::{Colormap, ColorRange};
+use maguey::render_gl::image::GrayImage;
+use maguey::render_gl::lighting::{DirectionalLight, Lighting, PointLight};
+use maguey::render_gl::resources::Resources;
+use maguey::render_gl::scene::Scene;
+use maguey::render_gl::Viewport;
+use maguey::wave_estimator::{
+    Boundaries, BoundaryCondition, DiagnosticsLog, Domain, PointSource, Settings, Shape, Stability, WaveEstimator,
+};
+use maguey::grid::Grid;
+use maguey::heightmap::Heightmap;
+use maguey::legend::Legend;
+use maguey::noise::{Fbm, Noise, Perlin, Ridged, Simplex};
+use maguey::simulation::{FieldSimulation, DampedWave, GrayScott, GrayScottParams, Heat};
 
-pub mod render_gl;
-mod triangle;
-mod game;
-mod grid;
-mod debug;
-mod wave_estimator;
+// Length of a tick of the simulation clock
+const TIME_STEP: f32 = 0.01;
+const GRID_SCALE: f32 = 1.0;
+const GRANULARITY: i32 = 30;
+// Environment variable holding the path of a CSV file to log the wave's diagnostics to
+const DIAGNOSTICS_LOG_VAR: &str = "WAVE_DIAGNOSTICS_LOG";
+// The wave simulations keep a snapshot every HISTORY_INTERVAL ticks, up to HISTORY_LENGTH of
+// them, which covers the last 30 seconds
+const HISTORY_LENGTH: usize = 600;
+const HISTORY_INTERVAL: u32 = 5;
+// Seed of the random surface, which stays the same from run to run
+const NOISE_SEED: u64 = 2019;
+// Files the E key exports the surface to, in every supported format
+const MESH_EXPORT_PATHS: [&str; 3] = ["surface.obj", "surface.ply", "surface.stl"];
+// Heights spanned by the colormap in its fixed range mode
+const FIXED_COLOR_RANGE: ColorRange = ColorRange::Fixed { min: -0.2, max: 0.2 };
+// Names of the scene nodes holding the surface and the colormap legend
+const GRID_NODE: &str = "grid";
+const LEGEND_NODE: &str = "legend";
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -27,53 +54,347 @@ pub enum Error {
     NoneObject(String),
 }
 
+// A simulation the demo can switch to, with how many units of its own time pass per second
+struct Simulation {
+    name: &'static str,
+    field: Box<dyn FieldSimulation>,
+    time_scale: f32,
+}
+
 struct Game {
-    grid: Option<Grid>,
-    estimator: Option<WaveEstimator>,
+    scene: Scene,
+    lighting: Lighting,
+    colormaps: Vec<Colormap>,
+    // Index of the colormap the grid is colored with, if any
+    colormap: Option<usize>,
+    simulations: Vec<Simulation>,
+    current: usize,
     start_time: SystemTime,
 }
 
 impl Game {
     fn new() -> Game {
-        Game{ grid: None, estimator: None, start_time: SystemTime::now() }
+        Game{
+            scene: Scene::new(),
+            lighting: Game::default_lighting(),
+            colormaps: Vec::new(),
+            colormap: None,
+            simulations: Vec::new(),
+            current: 0,
+            start_time: SystemTime::now(),
+        }
+    }
+
+    // Soft white light from above, with a warmer point light hovering over a corner of the grid
+    fn default_lighting() -> Lighting {
+        let mut lighting = Lighting::new(nalgebra_glm::vec3(0.15, 0.15, 0.15));
+        lighting.add_directional_light(DirectionalLight {
+            direction: nalgebra_glm::vec3(-0.3, -1.0, -0.2),
+            color: nalgebra_glm::vec3(0.7, 0.7, 0.7),
+        });
+        lighting.add_point_light(PointLight {
+            position: nalgebra_glm::vec3(0.8 * GRID_SCALE, 0.6, 0.8 * GRID_SCALE),
+            color: nalgebra_glm::vec3(0.6, 0.5, 0.3),
+            attenuation: nalgebra_glm::vec3(1.0, 0.5, 0.5),
+        });
+
+        lighting
+    }
+
+    // Colors the grid with the current colormap, or in a single color when there is none
+    fn update_colormap(&mut self) -> Result<(), Error> {
+        let colormaps = &self.colormaps;
+        let colormap = self.colormap.and_then(|i| colormaps.get(i));
+        let grid = self.scene.find_object_mut::<Grid>(GRID_NODE)
+            .ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+        grid.set_colormap(colormap.cloned());
+
+        let legend_node = self.scene.find(LEGEND_NODE).ok_or(Error::NoneObject(LEGEND_NODE.to_string()))?;
+        if let Some(colormap) = colormap {
+            let legend = self.scene.object_mut::<Legend>(legend_node)
+                .ok_or(Error::NoneObject(LEGEND_NODE.to_string()))?;
+            legend.set_colormap(colormap);
+        }
+        // The legend only shows while there is a colormap to explain
+        if let Some(node) = self.scene.node_mut(legend_node) {
+            node.visible = colormap.is_some();
+        }
+
+        Ok(())
     }
+
+    // Transform from the coordinates of the grid's vertices to the world's, as it is drawn
+    fn grid_world_matrix(&self) -> Result<nalgebra_glm::Mat4, Error> {
+        let node = self.scene.find(GRID_NODE).ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+        let grid = self.scene.object::<Grid>(node).ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+
+        Ok(self.scene.world_matrix(node) * grid.transform().matrix())
+    }
+
+    fn current_simulation(&mut self) -> Result<&mut Simulation, Error> {
+        self.simulations.get_mut(self.current).ok_or(Error::NoneObject("simulation".to_string()))
+    }
+
+    // Colors the obstacles of the current simulation on the grid
+    fn update_grid_mask(&mut self) -> Result<(), Error> {
+        let simulation = self.simulations.get(self.current)
+            .ok_or(Error::NoneObject("simulation".to_string()))?;
+        let field = &simulation.field;
+        let grid = self.scene.find_object_mut::<Grid>(GRID_NODE)
+            .ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+
+        grid.set_mask(|x, y| field.is_solid(x, y));
+        Ok(())
+    }
+}
+
+fn standing_wave(x: f32, y: f32) -> f32 {
+    0.2 * f32::sin(2.0 * std::f32::consts::PI*(x+1.0)) * f32::sin(2.0 * std::f32::consts::PI*(y+1.0))
+}
+
+// Wall across the domain with two narrow slits, lit by a row of sources that sends a plane wave
+// at it. Absorbing edges keep reflections off the domain boundary from washing out the
+// interference pattern.
+fn double_slit(settings: Settings) -> Result<WaveEstimator, failure::Error> {
+    let settings = Settings { boundaries: Boundaries::uniform(BoundaryCondition::Absorbing), ..settings };
+    let mut estimator = WaveEstimator::new_with_settings(GRANULARITY, 5.0, settings, |_, _| 0.0)?;
+
+    let wall = |y_min, y_max| Shape::Rect { x_min: -0.32, y_min, x_max: -0.28, y_max };
+    estimator.add_obstacle(wall(-1.0, -0.2));
+    estimator.add_obstacle(wall(-0.1, 0.1));
+    estimator.add_obstacle(wall(0.2, 1.0));
+    for k in -9..10 {
+        let y = k as f32 / 10.0;
+        estimator.add_source(PointSource { x: -0.8, y, amplitude: 0.2, frequency: 6.0, phase: 0.0 });
+    }
+
+    Ok(estimator)
+}
+
+// Tank whose obstacles come from the black pixels of an image stretched over the domain
+fn ripple_tank(res: &Resources, settings: Settings) -> Result<WaveEstimator, failure::Error> {
+    let image = GrayImage::from_res(res, "masks/ripple_tank.png")?;
+    let mut estimator = WaveEstimator::new_with_settings(GRANULARITY, 5.0, settings, |_, _| 0.0)?;
+
+    estimator.set_mask_from_image(&image, 0.5);
+    estimator.add_drop(-0.6, -0.6, 0.2, 0.05);
+
+    Ok(estimator)
+}
+
+// Ring shaped pulse drawn as an image, released from rest
+fn image_pulse(res: &Resources, settings: Settings) -> Result<WaveEstimator, failure::Error> {
+    let domain = Domain::square(GRANULARITY);
+    let heightmap = Heightmap::from_res(res, "heightmaps/ring_pulse.png", domain, 0.2)?;
+
+    Ok(WaveEstimator::new_with_domain(domain, 5.0, settings, |x, y| heightmap.height(x, y))?)
+}
+
+// Random rolling surface released from rest over a seabed of ridges, which slow the waves down
+// where they come close to the surface
+fn noise_surface(settings: Settings) -> Result<WaveEstimator, failure::Error> {
+    let mut surface = Fbm::new(Perlin::new(NOISE_SEED), 5);
+    surface.set_frequency(2.0);
+    let mut seabed = Ridged::new(Simplex::new(NOISE_SEED + 1), 4);
+    seabed.set_frequency(1.5);
+
+    let mut estimator = WaveEstimator::new_with_settings(
+        GRANULARITY, 5.0, settings, |x, y| 0.1 * surface.sample(x, y),
+    )?;
+    estimator.set_alpha_field(|x, y| 5.0 * (0.55 - 0.45 * seabed.sample(x, y)));
+
+    Ok(estimator)
 }
 
 impl game::BaseGame for Game {
     fn load(&mut self, res: &Resources, gl: &gl::Gl) -> Result<(), failure::Error> {
         // let triangle = triangle::Triangle::new(&res, &gl)?;
-        self.grid = Some(grid::Grid::new(&res, &gl, 1.0, 30)?);
-        self.estimator = Some(wave_estimator::WaveEstimator::new(30, 5.0, |x, y| {
-            0.2 * f32::sin(2.0 * std::f32::consts::PI*(x+1.0)) * f32::sin(2.0 * std::f32::consts::PI*(y+1.0))
-        }));
+        self.scene = Scene::new();
+        self.scene.add_object(GRID_NODE, Grid::new(&res, &gl, GRID_SCALE, GRANULARITY)?, None);
+        self.colormaps = vec![
+            Colormap::coolwarm(),
+            Colormap::viridis(),
+            Colormap::magma(),
+            Colormap::from_res(res, "colormaps/ocean.png")?,
+        ];
+        self.colormap = Some(0);
+        self.scene.add_object(LEGEND_NODE, Legend::new(&res, &gl, &self.colormaps[0])?, None);
+        self.update_colormap()?;
+        let settings = Settings {
+            stability: Stability::Substep { max_substeps: 16 },
+            dt: Some(TIME_STEP),
+            ..Settings::default()
+        };
+        let mut estimator = WaveEstimator::new_with_settings(
+            GRANULARITY, 5.0, settings, standing_wave,
+        )?;
+        #[cfg(feature = "parallel")]
+        estimator.set_parallel(true);
+        if let Ok(path) = std::env::var(DIAGNOSTICS_LOG_VAR) {
+            estimator.set_diagnostics_log(Some(DiagnosticsLog::new(Box::new(File::create(path)?))?));
+        }
+        let mut double_slit = double_slit(settings)?;
+        let mut ripple_tank = ripple_tank(res, settings)?;
+        let mut image_pulse = image_pulse(res, settings)?;
+        let mut noise_surface = noise_surface(settings)?;
+        let estimators = vec![&mut estimator, &mut double_slit, &mut ripple_tank, &mut image_pulse, &mut noise_surface];
+        for estimator in estimators {
+            estimator.enable_history(HISTORY_LENGTH, HISTORY_INTERVAL);
+        }
+
+        self.simulations = vec![
+            Simulation { name: "wave", field: Box::new(estimator), time_scale: 1.0 },
+            Simulation { name: "double slit", field: Box::new(double_slit), time_scale: 1.0 },
+            Simulation { name: "ripple tank", field: Box::new(ripple_tank), time_scale: 1.0 },
+            Simulation { name: "image pulse", field: Box::new(image_pulse), time_scale: 1.0 },
+            Simulation { name: "noise surface", field: Box::new(noise_surface), time_scale: 1.0 },
+            Simulation {
+                name: "damped wave",
+                field: Box::new(DampedWave::new(GRANULARITY, 5.0_f32.sqrt(), 0.5, standing_wave)),
+                time_scale: 1.0,
+            },
+            Simulation {
+                name: "heat",
+                field: Box::new(Heat::new(GRANULARITY, 0.01, standing_wave)),
+                time_scale: 1.0,
+            },
+            Simulation {
+                name: "gray-scott",
+                field: Box::new(GrayScott::new(
+                    GRANULARITY,
+                    GrayScottParams::mitosis(GRANULARITY),
+                    |x, y| if x.abs() < 0.15 && y.abs() < 0.15 { 0.25 } else { 0.0 },
+                )),
+                time_scale: 500.0,
+            },
+        ];
+        self.current = 0;
+        self.update_grid_mask()?;
 
         Ok(())
     }
 
-    fn update(&mut self, _viewport: &mut Viewport) -> Result<(), failure::Error> {
-        let mut estimator = self.estimator.as_mut().ok_or(Error::NoneObject("estimator".to_string()))?;
-        let mut grid = self.grid.as_mut().ok_or(Error::NoneObject("grid".to_string()))?;
+    fn update(&mut self, time: &FrameTime, _viewport: &mut Viewport) -> Result<(), failure::Error> {
+        let simulation = self.current_simulation()?;
+        simulation.field.step(time.dt * simulation.time_scale)?;
+
+        Ok(())
+    }
 
-        estimator.update(0.01);
+    fn render(&mut self, gl: &gl::Gl, _time: &FrameTime, viewport: &mut Viewport) -> Result<(), failure::Error> {
+        let field = &self.simulations[self.current].field;
+        let grid = self.scene.find_object_mut::<Grid>(GRID_NODE)
+            .ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
 
         grid.update_vertices(|x, y| {
-            estimator.get_val(x, y)
+            field.sample(x, y)
         });
 
+        // The scene gets drawn by the executor right after this
+        self.lighting.apply_uniforms(&gl, grid.get_program_id(), viewport.position)?;
+
         Ok(())
     }
 
-    fn render(&mut self, gl: &gl::Gl, viewport: &mut Viewport) -> Result<(), failure::Error> {
-        let mut grid = self.grid.as_mut().ok_or(Error::NoneObject("grid".to_string()))?;
+    fn scene(&mut self) -> Option<&mut Scene> {
+        Some(&mut self.scene)
+    }
+
+    fn on_click(&mut self, point: nalgebra_glm::Vec3) -> Result<(), failure::Error> {
+        // The vertices of the grid sit at the domain coordinates of their points, so undoing its
+        // placement in the world gives where the click landed in the domain
+        let local = nalgebra_glm::inverse(&self.grid_world_matrix()?) * point.push(1.0);
+        let simulation = self.current_simulation()?;
+
+        simulation.field.add_drop(local.x / local.w, local.z / local.w, 0.2, 0.05);
 
-        viewport.apply_uniforms(grid.get_program_id())?;
-        grid.render(&gl);
+        Ok(())
+    }
+
+    fn on_key_down(&mut self, keycode: sdl2::keyboard::Keycode) -> Result<(), failure::Error> {
+        if keycode == sdl2::keyboard::Keycode::Tab && !self.simulations.is_empty() {
+            self.current = (self.current + 1) % self.simulations.len();
+            println!("Switched to {} simulation", self.current_simulation()?.name);
+            self.update_grid_mask()?;
+        }
+        if keycode == sdl2::keyboard::Keycode::M {
+            let grid = self.scene.find_object_mut::<Grid>(GRID_NODE)
+                .ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+            grid.set_display_mode(grid.display_mode().next());
+            println!("Showing the grid as {:?}", grid.display_mode());
+        }
+        // Cycles through the colormaps, then a single color
+        if keycode == sdl2::keyboard::Keycode::C {
+            self.colormap = match self.colormap {
+                Some(i) if i + 1 < self.colormaps.len() => Some(i + 1),
+                Some(_) => None,
+                None if !self.colormaps.is_empty() => Some(0),
+                None => None,
+            };
+            self.update_colormap()?;
+        }
+        if keycode == sdl2::keyboard::Keycode::E {
+            let node = self.scene.find(GRID_NODE).ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+            let grid = self.scene.object::<Grid>(node).ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+            let mesh = grid.mesh(&self.scene.world_matrix(node));
+
+            for path in MESH_EXPORT_PATHS.iter() {
+                // A failed export is reported without ending the game
+                match mesh.save_to_file(Path::new(path)) {
+                    Ok(()) => println!("Exported the surface to {}", path),
+                    Err(e) => println!("{}", debug::failure_to_string(e.into())),
+                }
+            }
+        }
+        if keycode == sdl2::keyboard::Keycode::R {
+            let grid = self.scene.find_object_mut::<Grid>(GRID_NODE)
+                .ok_or(Error::NoneObject(GRID_NODE.to_string()))?;
+            grid.set_color_range(match grid.color_range() {
+                ColorRange::Symmetric => ColorRange::Auto,
+                ColorRange::Auto => FIXED_COLOR_RANGE,
+                ColorRange::Fixed { .. } => ColorRange::Symmetric,
+            });
+            println!("Coloring heights over the {:?} range", grid.color_range());
+        }
+
+        Ok(())
+    }
+
+    fn status(&self) -> Option<String> {
+        let simulation = self.simulations.get(self.current)?;
+
+        let mut status = match simulation.field.diagnostics() {
+            Some(diagnostics) => format!("{}: {}", simulation.name, diagnostics),
+            None => simulation.name.to_string(),
+        };
+        // Heights at the bottom and top of the legend
+        if let Some(grid) = self.scene.find_object::<Grid>(GRID_NODE) {
+            if let Some(colormap) = grid.colormap() {
+                let (min, max) = grid.value_range();
+                status += &format!("  {} [{:.4}, {:.4}]", colormap.name(), min, max);
+            }
+        }
+
+        Some(status)
+    }
+
+    fn scrub(&mut self, offset: isize) -> Result<bool, failure::Error> {
+        Ok(self.current_simulation()?.field.scrub(offset))
+    }
+
+    fn save_checkpoint(&mut self, path: &Path) -> Result<(), failure::Error> {
+        self.current_simulation()?.field.save_checkpoint(path)
+    }
 
+    fn load_checkpoint(&mut self, path: &Path) -> Result<(), failure::Error> {
+        self.current_simulation()?.field.load_checkpoint(path)?;
+        self.update_grid_mask()?;
         Ok(())
     }
 }
 
 fn main() {
     let mut game = game::GameExecutor::new(Game::new(), (1280, 800));
+    game.set_tick_rate(1.0 / TIME_STEP);
     game.run();
 }
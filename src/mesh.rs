@@ -0,0 +1,158 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use nalgebra_glm as glm;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "I/O error")]
+    Io(#[cause] io::Error),
+    #[fail(display = "Can not determine mesh format for file {}", path)]
+    UnknownFormat { path: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Self {
+        Error::Io(other)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlyEncoding {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+// Triangle mesh with a normal and a color in [0, 1]³ for every vertex. Triangles wind
+// counterclockwise when seen from the side their normals point to.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub positions: Vec<glm::Vec3>,
+    pub normals: Vec<glm::Vec3>,
+    pub colors: Vec<glm::Vec3>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    // Wavefront OBJ, with the vertex colors after the positions as most tools read them
+    pub fn save_obj(&self, writer: impl Write) -> Result<(), Error> {
+        let mut w = BufWriter::new(writer);
+
+        for (p, c) in self.positions.iter().zip(self.colors.iter()) {
+            writeln!(w, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.x, c.y, c.z)?;
+        }
+        for n in self.normals.iter() {
+            writeln!(w, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        // Indices start at 1, and every vertex uses the normal with the same index
+        for t in self.triangles.iter() {
+            let (a, b, c) = (t[0] + 1, t[1] + 1, t[2] + 1);
+            writeln!(w, "f {}//{} {}//{} {}//{}", a, a, b, b, c, c)?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    // Stanford PLY, with positions, normals and 8-bit colors on every vertex
+    pub fn save_ply(&self, writer: impl Write, encoding: PlyEncoding) -> Result<(), Error> {
+        let mut w = BufWriter::new(writer);
+
+        writeln!(w, "ply")?;
+        writeln!(w, "format {} 1.0", match encoding {
+            PlyEncoding::Ascii => "ascii",
+            PlyEncoding::BinaryLittleEndian => "binary_little_endian",
+        })?;
+        writeln!(w, "element vertex {}", self.positions.len())?;
+        for property in ["x", "y", "z", "nx", "ny", "nz"].iter() {
+            writeln!(w, "property float {}", property)?;
+        }
+        for property in ["red", "green", "blue"].iter() {
+            writeln!(w, "property uchar {}", property)?;
+        }
+        writeln!(w, "element face {}", self.triangles.len())?;
+        writeln!(w, "property list uchar uint vertex_indices")?;
+        writeln!(w, "end_header")?;
+
+        let vertices = self.positions.iter().zip(self.normals.iter()).zip(self.colors.iter());
+        match encoding {
+            PlyEncoding::Ascii => {
+                for ((p, n), c) in vertices {
+                    let c = to_bytes(c);
+                    writeln!(w, "{} {} {} {} {} {} {} {} {}", p.x, p.y, p.z, n.x, n.y, n.z, c[0], c[1], c[2])?;
+                }
+                for t in self.triangles.iter() {
+                    writeln!(w, "3 {} {} {}", t[0], t[1], t[2])?;
+                }
+            },
+            PlyEncoding::BinaryLittleEndian => {
+                for ((p, n), c) in vertices {
+                    for &x in [p.x, p.y, p.z, n.x, n.y, n.z].iter() {
+                        w.write_all(&x.to_le_bytes())?;
+                    }
+                    w.write_all(&to_bytes(c))?;
+                }
+                for t in self.triangles.iter() {
+                    w.write_all(&[3])?;
+                    for &i in t.iter() {
+                        w.write_all(&i.to_le_bytes())?;
+                    }
+                }
+            },
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    // Binary STL, which has no room for shared vertices, vertex normals or colors. Every triangle
+    // gets its face normal.
+    pub fn save_stl(&self, writer: impl Write) -> Result<(), Error> {
+        let mut w = BufWriter::new(writer);
+
+        let mut header = [0u8; 80];
+        let title = b"surface exported by maguey";
+        header[..title.len()].copy_from_slice(title);
+        w.write_all(&header)?;
+        w.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+
+        for t in self.triangles.iter() {
+            let (a, b, c) = (self.positions[t[0] as usize], self.positions[t[1] as usize], self.positions[t[2] as usize]);
+            let cross = (b - a).cross(&(c - a));
+            // Degenerate triangles get a zero normal, which readers recompute
+            let normal = if cross.norm() > 0.0 { glm::normalize(&cross) } else { cross };
+
+            for v in [normal, a, b, c].iter() {
+                for &x in [v.x, v.y, v.z].iter() {
+                    w.write_all(&x.to_le_bytes())?;
+                }
+            }
+            // Attribute byte count, which nothing uses
+            w.write_all(&[0, 0])?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    // Picks the format from the extension of `path`: .obj, .ply (binary) or .stl
+    pub fn save_to_file(&self, path: &Path) -> Result<(), Error> {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_ref().map(|e| e.as_str()) {
+            Some("obj") => self.save_obj(File::create(path)?),
+            Some("ply") => self.save_ply(File::create(path)?, PlyEncoding::BinaryLittleEndian),
+            Some("stl") => self.save_stl(File::create(path)?),
+            _ => Err(Error::UnknownFormat { path: path.to_string_lossy().into_owned() }),
+        }
+    }
+}
+
+fn to_bytes(color: &glm::Vec3) -> [u8; 3] {
+    let to_byte = |c: f32| (c.max(0.0).min(1.0) * 255.0).round() as u8;
+
+    [to_byte(color.x), to_byte(color.y), to_byte(color.z)]
+}
@@ -0,0 +1,317 @@
+// Coherent noise over the plane, for random but smooth surfaces. Every generator is built from a
+// seed and gives the same values for the same seed, whatever the platform.
+
+// A function of position that varies smoothly over distances of about 1, with values roughly
+// within [-1, 1]. Scale the coordinates passed in to change the size of the features.
+pub trait Noise {
+    fn sample(&self, x: f32, y: f32) -> f32;
+}
+
+impl<'a, N: Noise + ?Sized> Noise for &'a N {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        (**self).sample(x, y)
+    }
+}
+
+// Shuffled 0..256 repeated twice, so that chained lookups never have to wrap
+struct Permutation {
+    table: [u8; 512],
+}
+
+impl Permutation {
+    fn new(seed: u64) -> Permutation {
+        let mut values = [0u8; 256];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+
+        // Fisher-Yates shuffle driven by SplitMix64
+        let mut state = seed;
+        for i in (1..256).rev() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+
+            values.swap(i, (z % (i as u64 + 1)) as usize);
+        }
+
+        let mut table = [0u8; 512];
+        for i in 0..512 {
+            table[i] = values[i & 255];
+        }
+
+        Permutation { table }
+    }
+
+    // Pseudo-random byte for the lattice point (x, y)
+    fn hash(&self, x: i32, y: i32) -> u8 {
+        let x = (x & 255) as usize;
+        let y = (y & 255) as usize;
+
+        self.table[self.table[x] as usize + y]
+    }
+}
+
+// 6t⁵ - 15t⁴ + 10t³, which eases in and out with no jump in the first two derivatives
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+// Dot product of (x, y) with one of eight gradients picked by the hash
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+// Ken Perlin's gradient noise, which is 0 on every integer lattice point
+pub struct Perlin {
+    permutation: Permutation,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        Perlin { permutation: Permutation::new(seed) }
+    }
+}
+
+impl Noise for Perlin {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (xf, yf) = (x - x0, y - y0);
+        let (xi, yi) = (x0 as i32, y0 as i32);
+        let p = &self.permutation;
+
+        let (u, v) = (fade(xf), fade(yf));
+        let bottom = lerp(
+            gradient(p.hash(xi, yi), xf, yf),
+            gradient(p.hash(xi + 1, yi), xf - 1.0, yf),
+            u,
+        );
+        let top = lerp(
+            gradient(p.hash(xi, yi + 1), xf, yf - 1.0),
+            gradient(p.hash(xi + 1, yi + 1), xf - 1.0, yf - 1.0),
+            u,
+        );
+
+        lerp(bottom, top, v)
+    }
+}
+
+// Gradient noise over a lattice of triangles, with fewer directional artifacts than Perlin noise
+pub struct Simplex {
+    permutation: Permutation,
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Simplex {
+        Simplex { permutation: Permutation::new(seed) }
+    }
+
+    // Contribution of the corner at offset (x, y) from the sample point
+    fn corner(&self, hash: u8, x: f32, y: f32) -> f32 {
+        let t = 0.5 - x * x - y * y;
+
+        if t < 0.0 { 0.0 } else { t * t * t * t * gradient(hash, x, y) }
+    }
+}
+
+impl Noise for Simplex {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        // Factors that skew the plane onto a square lattice and back
+        let skew = 0.5 * (3.0_f32.sqrt() - 1.0);
+        let unskew = (3.0 - 3.0_f32.sqrt()) / 6.0;
+
+        let s = (x + y) * skew;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * unskew;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+
+        // Which of the two triangles of the skewed cell the point is in
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (x1, y1) = (x0 - i1 as f32 + unskew, y0 - j1 as f32 + unskew);
+        let (x2, y2) = (x0 - 1.0 + 2.0 * unskew, y0 - 1.0 + 2.0 * unskew);
+
+        let (i, j) = (i as i32, j as i32);
+        let p = &self.permutation;
+        let n = self.corner(p.hash(i, j), x0, y0)
+            + self.corner(p.hash(i + i1, j + j1), x1, y1)
+            + self.corner(p.hash(i + 1, j + 1), x2, y2);
+
+        // Brings the peaks to about ±1
+        70.0 * n
+    }
+}
+
+// Random values on the integer lattice, smoothly interpolated in between
+pub struct Value {
+    permutation: Permutation,
+}
+
+impl Value {
+    pub fn new(seed: u64) -> Value {
+        Value { permutation: Permutation::new(seed) }
+    }
+
+    fn lattice(&self, x: i32, y: i32) -> f32 {
+        self.permutation.hash(x, y) as f32 / 127.5 - 1.0
+    }
+}
+
+impl Noise for Value {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (u, v) = (fade(x - x0), fade(y - y0));
+        let (xi, yi) = (x0 as i32, y0 as i32);
+
+        let bottom = lerp(self.lattice(xi, yi), self.lattice(xi + 1, yi), u);
+        let top = lerp(self.lattice(xi, yi + 1), self.lattice(xi + 1, yi + 1), u);
+
+        lerp(bottom, top, v)
+    }
+}
+
+// Fractal Brownian motion: octaves of a noise at rising frequencies and falling amplitudes, for
+// detail at every scale like that of natural terrain
+pub struct Fbm<N: Noise> {
+    noise: N,
+    octaves: u32,
+    frequency: f32,
+    // Factor between the frequencies of successive octaves
+    lacunarity: f32,
+    // Factor between the amplitudes of successive octaves
+    gain: f32,
+}
+
+impl<N: Noise> Fbm<N> {
+    pub fn new(noise: N, octaves: u32) -> Fbm<N> {
+        Fbm { noise, octaves: octaves.max(1), frequency: 1.0, lacunarity: 2.0, gain: 0.5 }
+    }
+
+    // Frequency of the first octave
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    pub fn set_lacunarity(&mut self, lacunarity: f32) {
+        self.lacunarity = lacunarity;
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain;
+    }
+
+    // Sums `f` of every octave weighted by its amplitude, divided by the sum of the amplitudes
+    fn accumulate(&self, x: f32, y: f32, f: impl Fn(f32) -> f32) -> f32 {
+        let (mut sum, mut total) = (0.0, 0.0);
+        let (mut frequency, mut amplitude) = (self.frequency, 1.0);
+
+        for octave in 0..self.octaves {
+            // Shifting every octave keeps the lattices of the octaves from lining up at the origin
+            let shift = 17.3 * octave as f32;
+            sum += amplitude * f(self.noise.sample(frequency * x + shift, frequency * y - shift));
+            total += amplitude;
+
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+
+        sum / total
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.accumulate(x, y, |n| n)
+    }
+}
+
+// Fractal noise made of sharp crests where the noise crosses 0, like mountain ridges
+pub struct Ridged<N: Noise> {
+    fbm: Fbm<N>,
+}
+
+impl<N: Noise> Ridged<N> {
+    pub fn new(noise: N, octaves: u32) -> Ridged<N> {
+        Ridged { fbm: Fbm::new(noise, octaves) }
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.fbm.set_frequency(frequency);
+    }
+
+    pub fn set_lacunarity(&mut self, lacunarity: f32) {
+        self.fbm.set_lacunarity(lacunarity);
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.fbm.set_gain(gain);
+    }
+}
+
+impl<N: Noise> Noise for Ridged<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        // Crests of (1 - |n|)² in [0, 1], stretched back over [-1, 1]
+        2.0 * self.fbm.accumulate(x, y, |n| (1.0 - n.abs().min(1.0)).powi(2)) - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fbm, Noise, Perlin, Ridged, Simplex, Value};
+
+    // Bits of the samples over a patch of the plane, away from the integer lattice where
+    // gradient noise is always 0
+    fn samples(noise: impl Noise) -> Vec<u32> {
+        let mut samples = Vec::new();
+        for i in 0..16 {
+            for j in 0..16 {
+                samples.push(noise.sample(0.37 * i as f32 + 0.11, 0.29 * j as f32 - 3.7).to_bits());
+            }
+        }
+        samples
+    }
+
+    fn assert_deterministic<N: Noise>(build: impl Fn(u64) -> N) {
+        assert_eq!(samples(build(7)), samples(build(7)));
+        assert_ne!(samples(build(7)), samples(build(8)));
+    }
+
+    #[test]
+    fn perlin_depends_only_on_the_seed() {
+        assert_deterministic(Perlin::new);
+    }
+
+    #[test]
+    fn simplex_depends_only_on_the_seed() {
+        assert_deterministic(Simplex::new);
+    }
+
+    #[test]
+    fn value_depends_only_on_the_seed() {
+        assert_deterministic(Value::new);
+    }
+
+    #[test]
+    fn fbm_depends_only_on_the_seed() {
+        assert_deterministic(|seed| Fbm::new(Perlin::new(seed), 4));
+    }
+
+    #[test]
+    fn ridged_depends_only_on_the_seed() {
+        assert_deterministic(|seed| Ridged::new(Simplex::new(seed), 4));
+    }
+}
@@ -4,6 +4,25 @@ pub trait BufferType {
     const BUFFER_TYPE: gl::types::GLuint;
 }
 
+// Hint of how often the contents of a buffer get replaced: set once and drawn many times,
+// modified now and then, or rewritten before nearly every draw
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Usage {
+    Static,
+    Dynamic,
+    Stream,
+}
+
+impl Usage {
+    fn gl_enum(self) -> gl::types::GLenum {
+        match self {
+            Usage::Static => gl::STATIC_DRAW,
+            Usage::Dynamic => gl::DYNAMIC_DRAW,
+            Usage::Stream => gl::STREAM_DRAW,
+        }
+    }
+}
+
 pub struct Buffer<B> where B: BufferType {
     gl: gl::Gl,
     vbo: gl::types::GLuint,
@@ -30,17 +49,106 @@ impl<B> Buffer<B> where B: BufferType {
         }
     }
 
+    pub fn static_draw_data<T>(&self, data: &[T]) {
+        self.data(data, Usage::Static);
+    }
+
     pub fn dynamic_draw_data<T>(&self, data: &[T]) {
+        self.data(data, Usage::Dynamic);
+    }
+
+    pub fn stream_draw_data<T>(&self, data: &[T]) {
+        self.data(data, Usage::Stream);
+    }
+
+    // Replaces the storage of the bound buffer with a copy of `data`
+    pub fn data<T>(&self, data: &[T], usage: Usage) {
         unsafe {
             self.gl.BufferData(
                 B::BUFFER_TYPE,  // target
                 (data.len() * ::std::mem::size_of::<T>()) as gl::types::GLsizeiptr,  // size of
                 // data in bytes
                 data.as_ptr() as *const gl::types::GLvoid,  // pointer to data
-                gl::DYNAMIC_DRAW,  // usage
+                usage.gl_enum(),  // usage
             );
         }
     }
+
+    // Overwrites part of the storage of the bound buffer with `data`, starting `offset` elements
+    // in. The storage has to be large enough already.
+    pub fn sub_data<T>(&self, offset: usize, data: &[T]) {
+        unsafe {
+            self.gl.BufferSubData(
+                B::BUFFER_TYPE,  // target
+                (offset * ::std::mem::size_of::<T>()) as gl::types::GLintptr,  // offset in bytes
+                (data.len() * ::std::mem::size_of::<T>()) as gl::types::GLsizeiptr,  // size of
+                // data in bytes
+                data.as_ptr() as *const gl::types::GLvoid,  // pointer to data
+            );
+        }
+    }
+
+    // Gives the bound buffer fresh, uninitialized storage for `len` elements. Draws still reading
+    // the old storage keep it until they finish, so writing to the new one right away does not
+    // wait for them.
+    pub fn orphan<T>(&self, len: usize, usage: Usage) {
+        unsafe {
+            self.gl.BufferData(
+                B::BUFFER_TYPE,  // target
+                (len * ::std::mem::size_of::<T>()) as gl::types::GLsizeiptr,  // size in bytes
+                ::std::ptr::null(),  // no data to copy
+                usage.gl_enum(),  // usage
+            );
+        }
+    }
+
+    // Maps `len` elements of the bound buffer, starting `offset` elements in, for writing. Their
+    // previous contents are discarded. The buffer has to stay bound until the mapping is dropped,
+    // which fails when the driver could not map it.
+    pub fn map_range<T: Copy>(&self, offset: usize, len: usize) -> Option<MappedRange<'_, B, T>> {
+        let ptr = unsafe {
+            self.gl.MapBufferRange(
+                B::BUFFER_TYPE,  // target
+                (offset * ::std::mem::size_of::<T>()) as gl::types::GLintptr,  // offset in bytes
+                (len * ::std::mem::size_of::<T>()) as gl::types::GLsizeiptr,  // length in bytes
+                gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_RANGE_BIT,  // access
+            )
+        };
+
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(MappedRange { buffer: self, ptr: ptr as *mut T, len })
+    }
+}
+
+// Elements of a buffer mapped into memory, unmapped when dropped
+pub struct MappedRange<'a, B, T> where B: BufferType {
+    buffer: &'a Buffer<B>,
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<'a, B, T: Copy> MappedRange<'a, B, T> where B: BufferType {
+    // Copies `data` into the mapped elements, starting `offset` elements in. The mapping is
+    // write-only, its previous contents being undefined, so there is no way to read it back.
+    pub fn write(&mut self, offset: usize, data: &[T]) {
+        assert!(
+            offset <= self.len && data.len() <= self.len - offset,
+            "writing {} elements at {} past the {} mapped", data.len(), offset, self.len,
+        );
+
+        unsafe { ::std::ptr::copy_nonoverlapping(data.as_ptr(), self.ptr.add(offset), data.len()) };
+    }
+}
+
+impl<'a, B, T> Drop for MappedRange<'a, B, T> where B: BufferType {
+    fn drop(&mut self) {
+        // Fails when the contents got lost while mapped, e.g. on a display mode change, in which
+        // case they get rewritten on the next update anyway
+        unsafe { self.buffer.gl.UnmapBuffer(B::BUFFER_TYPE) };
+    }
 }
 
 impl<B> Drop for Buffer<B> where B: BufferType {
@@ -20,7 +20,8 @@ impl ColorBuffer {
         unsafe { gl.ClearColor(self.color.x, self.color.y, self.color.z, 1.0) };
     }
 
+    // Clears the depth buffer along with the color, ready for the next frame
     pub fn clear(&self, gl: &gl::Gl) {
-        unsafe { gl.Clear(gl::COLOR_BUFFER_BIT) };
+        unsafe { gl.Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT) };
     }
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+use nalgebra_glm as glm;
+use crate::render_gl::image::{self, RgbImage};
+use crate::render_gl::resources::Resources;
+
+// Stops of the built-in maps, evenly spaced from the lowest value to the highest, in 8-bit sRGB
+const VIRIDIS: [(u8, u8, u8); 8] = [
+    (68, 1, 84), (70, 50, 127), (54, 92, 141), (39, 127, 143),
+    (31, 162, 136), (74, 194, 110), (160, 219, 57), (254, 232, 37),
+];
+const MAGMA: [(u8, u8, u8); 9] = [
+    (0, 0, 4), (28, 16, 68), (79, 18, 123), (129, 37, 129), (181, 54, 122),
+    (229, 80, 100), (251, 135, 97), (254, 194, 135), (252, 253, 191),
+];
+const COOLWARM: [(u8, u8, u8); 9] = [
+    (59, 76, 192), (98, 130, 234), (141, 176, 254), (184, 208, 249), (221, 221, 221),
+    (245, 196, 173), (244, 154, 123), (222, 96, 77), (180, 4, 38),
+];
+
+// Maps values in [0, 1] to colors, interpolating linearly between evenly spaced stops
+#[derive(Clone, Debug)]
+pub struct Colormap {
+    name: String,
+    stops: Vec<glm::Vec3>,
+}
+
+impl Colormap {
+    // Map going through `stops` in order, which must not be empty
+    pub fn new(name: &str, stops: Vec<glm::Vec3>) -> Colormap {
+        assert!(!stops.is_empty(), "a colormap needs at least one color");
+
+        Colormap { name: name.into(), stops }
+    }
+
+    // Perceptually uniform map from dark blue through green to yellow
+    pub fn viridis() -> Colormap {
+        Colormap::from_bytes("viridis", &VIRIDIS)
+    }
+
+    // Perceptually uniform map from black through purple and orange to pale yellow
+    pub fn magma() -> Colormap {
+        Colormap::from_bytes("magma", &MAGMA)
+    }
+
+    // Diverging map from blue to red through light gray, for values on both sides of 0
+    pub fn coolwarm() -> Colormap {
+        Colormap::from_bytes("coolwarm", &COOLWARM)
+    }
+
+    // Gradient read from left to right along the middle row of an image, such as a 256x1 PNG
+    pub fn from_res(res: &Resources, name: &str) -> Result<Colormap, image::Error> {
+        let image = RgbImage::from_res(res, name)?;
+        let row = image.height() / 2;
+        let stops = (0..image.width())
+            .map(|col| image.get(col, row))
+            .map(|(r, g, b)| glm::vec3(r, g, b))
+            .collect();
+
+        Ok(Colormap::new(name, stops))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Color of `t`, clamped to [0, 1]
+    pub fn color(&self, t: f32) -> glm::Vec3 {
+        let last = self.stops.len() - 1;
+        let position = t.max(0.0).min(1.0) * last as f32;
+        let i = position.floor() as usize;
+        let fraction = position - i as f32;
+
+        if i == last {
+            return self.stops[last];
+        }
+
+        glm::lerp(&self.stops[i], &self.stops[i + 1], fraction)
+    }
+
+    fn from_bytes(name: &str, stops: &[(u8, u8, u8)]) -> Colormap {
+        let to_unit = |c: u8| c as f32 / std::u8::MAX as f32;
+
+        Colormap::new(name, stops.iter().map(|&(r, g, b)| glm::vec3(to_unit(r), to_unit(g), to_unit(b))).collect())
+    }
+}
+
+// Which values get mapped to the ends of a colormap
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorRange {
+    // From the lowest to the highest value currently shown
+    Auto,
+    // From -m to m, where m is the largest magnitude currently shown, keeping 0 in the middle
+    Symmetric,
+    Fixed { min: f32, max: f32 },
+}
+
+impl ColorRange {
+    // Range of `values` that spans the colormap, never empty
+    pub fn resolve(&self, values: impl Iterator<Item=f32>) -> (f32, f32) {
+        let (min, max) = match *self {
+            ColorRange::Auto => values.fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            }),
+            ColorRange::Symmetric => {
+                let m = values.fold(0.0_f32, |m, v| m.max(v.abs()));
+                (-m, m)
+            },
+            ColorRange::Fixed { min, max } => (min, max),
+        };
+
+        if !(max - min > std::f32::EPSILON) {
+            let center = if min.is_finite() { 0.5 * (min + max) } else { 0.0 };
+            return (center - 0.5, center + 0.5);
+        }
+
+        (min, max)
+    }
+}
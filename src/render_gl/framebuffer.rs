@@ -0,0 +1,459 @@
+use gl;
+use crate::render_gl::texture::{Texture2D, TextureFormat};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Framebuffer is incomplete: {:?}", _0)]
+    Incomplete(Incompleteness),
+    #[fail(display = "Can not attach {}x{} storage to a {}x{} framebuffer", width, height, framebuffer_width, framebuffer_height)]
+    SizeMismatch {
+        width: u32,
+        height: u32,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+    },
+    #[fail(display = "Can not attach {:?} storage as {:?}", format, attachment)]
+    IncompatibleFormat {
+        format: TextureFormat,
+        attachment: Attachment,
+    },
+    #[fail(display = "Color attachment {} is past the {} supported", index, max)]
+    TooManyColorAttachments {
+        index: u32,
+        max: u32,
+    },
+    #[fail(display = "Framebuffer has no color attachment {}", _0)]
+    MissingColorAttachment(u32),
+    #[fail(
+        display = "Can not read {}x{} pixels at ({}, {}) from a {}x{} framebuffer",
+        width, height, x, y, framebuffer_width, framebuffer_height,
+    )]
+    OutOfBounds {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+    },
+}
+
+// Why the driver refused a framebuffer, from glCheckFramebufferStatus
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Incompleteness {
+    Undefined,
+    IncompleteAttachment,
+    MissingAttachment,
+    IncompleteDrawBuffer,
+    IncompleteReadBuffer,
+    Unsupported,
+    IncompleteMultisample,
+    IncompleteLayerTargets,
+    Unknown(gl::types::GLenum),
+}
+
+impl Incompleteness {
+    fn from_status(status: gl::types::GLenum) -> Incompleteness {
+        match status {
+            gl::FRAMEBUFFER_UNDEFINED => Incompleteness::Undefined,
+            gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Incompleteness::IncompleteAttachment,
+            gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => Incompleteness::MissingAttachment,
+            gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => Incompleteness::IncompleteDrawBuffer,
+            gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => Incompleteness::IncompleteReadBuffer,
+            gl::FRAMEBUFFER_UNSUPPORTED => Incompleteness::Unsupported,
+            gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => Incompleteness::IncompleteMultisample,
+            gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => Incompleteness::IncompleteLayerTargets,
+            status => Incompleteness::Unknown(status),
+        }
+    }
+}
+
+// Point of a framebuffer that storage gets attached to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Attachment {
+    // Output `n` of the fragment shader
+    Color(u32),
+    Depth,
+    DepthStencil,
+}
+
+impl Attachment {
+    fn gl_enum(self) -> gl::types::GLenum {
+        match self {
+            Attachment::Color(index) => gl::COLOR_ATTACHMENT0 + index,
+            Attachment::Depth => gl::DEPTH_ATTACHMENT,
+            Attachment::DepthStencil => gl::DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+
+    fn accepts(self, format: TextureFormat) -> bool {
+        match self {
+            Attachment::Color(_) => !format.is_depth(),
+            Attachment::Depth => format.is_depth(),
+            Attachment::DepthStencil => format.has_stencil(),
+        }
+    }
+}
+
+// Storage that can be drawn to but not sampled, for depth and stencil that never get read back
+pub struct Renderbuffer {
+    gl: gl::Gl,
+    id: gl::types::GLuint,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+}
+
+impl Renderbuffer {
+    pub fn new(gl: &gl::Gl, width: u32, height: u32, format: TextureFormat) -> Renderbuffer {
+        let mut id: gl::types::GLuint = 0;
+        unsafe { gl.GenRenderbuffers(1, &mut id) };
+
+        let mut renderbuffer = Renderbuffer { gl: gl.clone(), id, width: 0, height: 0, format };
+        renderbuffer.resize(width, height);
+
+        renderbuffer
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    // Replaces the storage with uninitialized storage of the new size
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+
+        unsafe {
+            self.gl.BindRenderbuffer(gl::RENDERBUFFER, self.id);
+            self.gl.RenderbufferStorage(
+                gl::RENDERBUFFER,  // target
+                self.format.internal_format(),  // format of the storage
+                width as gl::types::GLsizei,
+                height as gl::types::GLsizei,
+            );
+            self.gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteRenderbuffers(1, &self.id) };
+    }
+}
+
+enum Storage {
+    Texture(Texture2D),
+    Renderbuffer(Renderbuffer),
+}
+
+impl Storage {
+    fn size(&self) -> (u32, u32) {
+        match self {
+            Storage::Texture(texture) => (texture.width(), texture.height()),
+            Storage::Renderbuffer(renderbuffer) => (renderbuffer.width(), renderbuffer.height()),
+        }
+    }
+
+    fn format(&self) -> TextureFormat {
+        match self {
+            Storage::Texture(texture) => texture.format(),
+            Storage::Renderbuffer(renderbuffer) => renderbuffer.format(),
+        }
+    }
+}
+
+// Offscreen target to draw to instead of the window, owning the storage attached to it. Rendering
+// into textures lets later passes sample the result, e.g. for post-processing, and reading the
+// pixels back gives screenshots and picking.
+pub struct Framebuffer {
+    gl: gl::Gl,
+    id: gl::types::GLuint,
+    width: u32,
+    height: u32,
+    attachments: Vec<(Attachment, Storage)>,
+}
+
+impl Framebuffer {
+    // Framebuffer with nothing attached yet, which is incomplete until something is
+    pub fn new(gl: &gl::Gl, width: u32, height: u32) -> Framebuffer {
+        let mut id: gl::types::GLuint = 0;
+        unsafe { gl.GenFramebuffers(1, &mut id) };
+
+        Framebuffer { gl: gl.clone(), id, width, height, attachments: Vec::new() }
+    }
+
+    // Framebuffer drawing colors into a texture, with a depth buffer for the depth test
+    pub fn new_with_depth(
+        gl: &gl::Gl,
+        width: u32,
+        height: u32,
+        color_format: TextureFormat,
+    ) -> Result<Framebuffer, Error> {
+        let mut framebuffer = Framebuffer::new(gl, width, height);
+        framebuffer.attach_texture(Attachment::Color(0), Texture2D::new(gl, width, height, color_format))?;
+        framebuffer.attach_renderbuffer(
+            Attachment::Depth,
+            Renderbuffer::new(gl, width, height, TextureFormat::Depth24),
+        )?;
+        framebuffer.check()?;
+
+        Ok(framebuffer)
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Attaches the texture in place of whatever was attached at the same point
+    pub fn attach_texture(&mut self, attachment: Attachment, texture: Texture2D) -> Result<(), Error> {
+        self.attach(attachment, Storage::Texture(texture))
+    }
+
+    pub fn attach_renderbuffer(&mut self, attachment: Attachment, renderbuffer: Renderbuffer) -> Result<(), Error> {
+        self.attach(attachment, Storage::Renderbuffer(renderbuffer))
+    }
+
+    // Texture attached at the point, if a texture is attached there
+    pub fn texture(&self, attachment: Attachment) -> Option<&Texture2D> {
+        match self.storage(attachment)? {
+            Storage::Texture(texture) => Some(texture),
+            Storage::Renderbuffer(_) => None,
+        }
+    }
+
+    pub fn renderbuffer(&self, attachment: Attachment) -> Option<&Renderbuffer> {
+        match self.storage(attachment)? {
+            Storage::Renderbuffer(renderbuffer) => Some(renderbuffer),
+            Storage::Texture(_) => None,
+        }
+    }
+
+    // Fails with the reason the driver gives when the framebuffer can not be drawn to
+    pub fn check(&self) -> Result<(), Error> {
+        let status = self.while_bound(|gl| unsafe { gl.CheckFramebufferStatus(gl::FRAMEBUFFER) });
+
+        if status == gl::FRAMEBUFFER_COMPLETE {
+            Ok(())
+        } else {
+            Err(Error::Incomplete(Incompleteness::from_status(status)))
+        }
+    }
+
+    // Directs drawing to the framebuffer, over the whole of it
+    pub fn bind(&self) {
+        unsafe {
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            self.gl.Viewport(0, 0, self.width as gl::types::GLsizei, self.height as gl::types::GLsizei);
+        }
+    }
+
+    // Directs drawing back to the window, whose viewport has to be set again with
+    // `Viewport::set_used`
+    pub fn unbind(&self) {
+        unsafe { self.gl.BindFramebuffer(gl::FRAMEBUFFER, 0) };
+    }
+
+    // Gives every attachment uninitialized storage of the new size, e.g. to follow the window
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+
+        for (_, storage) in self.attachments.iter_mut() {
+            match storage {
+                Storage::Texture(texture) => texture.resize(width, height),
+                Storage::Renderbuffer(renderbuffer) => renderbuffer.resize(width, height),
+            }
+        }
+    }
+
+    // Colors of a rectangle of color attachment `index` as 8 bit RGBA, a row at a time from the
+    // bottom of the rectangle up
+    pub fn read_rgba8(&self, index: u32, x: u32, y: u32, width: u32, height: u32) -> Result<Vec<u8>, Error> {
+        self.read_color(index, (x, y, width, height), 4, gl::RGBA, gl::UNSIGNED_BYTE)
+    }
+
+    // Red channel of a rectangle of color attachment `index`, e.g. the ids of the objects drawn
+    // into an R32F texture for picking
+    pub fn read_red_f32(&self, index: u32, x: u32, y: u32, width: u32, height: u32) -> Result<Vec<f32>, Error> {
+        self.read_color(index, (x, y, width, height), 1, gl::RED, gl::FLOAT)
+    }
+
+    // Reads the rectangle (x, y, width, height) with `channels` values of type T per pixel, which
+    // have to match `format` and `data_type`
+    fn read_color<T: Copy + Default>(
+        &self,
+        index: u32,
+        (x, y, width, height): (u32, u32, u32, u32),
+        channels: usize,
+        format: gl::types::GLenum,
+        data_type: gl::types::GLenum,
+    ) -> Result<Vec<T>, Error> {
+        if self.storage(Attachment::Color(index)).is_none() {
+            return Err(Error::MissingColorAttachment(index));
+        }
+
+        let out_of_bounds = Error::OutOfBounds {
+            x,
+            y,
+            width,
+            height,
+            framebuffer_width: self.width,
+            framebuffer_height: self.height,
+        };
+        let inside = |start: u32, len: u32, end: u32| start.checked_add(len).map_or(false, |last| last <= end);
+        if !inside(x, width, self.width) || !inside(y, height, self.height) {
+            return Err(out_of_bounds);
+        }
+        // The driver writes exactly this many values, so they all have to fit in the vector
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(channels))
+            .ok_or(out_of_bounds)?;
+        let mut values = vec![T::default(); len];
+
+        self.while_bound(|gl| unsafe {
+            gl.ReadBuffer(gl::COLOR_ATTACHMENT0 + index);
+            // Rows are packed tightly, whatever their length
+            gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl.ReadPixels(
+                x as gl::types::GLint,
+                y as gl::types::GLint,
+                width as gl::types::GLsizei,
+                height as gl::types::GLsizei,
+                format,  // format of the pixel data
+                data_type,  // type of the pixel data
+                values.as_mut_ptr() as *mut gl::types::GLvoid,  // where to write the pixel data
+            );
+            gl.PixelStorei(gl::PACK_ALIGNMENT, 4);
+        });
+
+        Ok(values)
+    }
+
+    fn storage(&self, attachment: Attachment) -> Option<&Storage> {
+        self.attachments.iter().find(|(a, _)| *a == attachment).map(|(_, storage)| storage)
+    }
+
+    fn attach(&mut self, attachment: Attachment, storage: Storage) -> Result<(), Error> {
+        let (width, height) = storage.size();
+        if (width, height) != (self.width, self.height) {
+            return Err(Error::SizeMismatch {
+                width,
+                height,
+                framebuffer_width: self.width,
+                framebuffer_height: self.height,
+            });
+        }
+        if !attachment.accepts(storage.format()) {
+            return Err(Error::IncompatibleFormat { format: storage.format(), attachment });
+        }
+        if let Attachment::Color(index) = attachment {
+            let mut max: gl::types::GLint = 0;
+            unsafe { self.gl.GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max) };
+            if index >= max as u32 {
+                return Err(Error::TooManyColorAttachments { index, max: max as u32 });
+            }
+        }
+
+        self.while_bound(|gl| unsafe {
+            match &storage {
+                Storage::Texture(texture) => gl.FramebufferTexture2D(
+                    gl::FRAMEBUFFER,  // target
+                    attachment.gl_enum(),  // attachment point
+                    gl::TEXTURE_2D,  // texture target
+                    texture.id(),
+                    0,  // mipmap level
+                ),
+                Storage::Renderbuffer(renderbuffer) => gl.FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,  // target
+                    attachment.gl_enum(),  // attachment point
+                    gl::RENDERBUFFER,  // renderbuffer target
+                    renderbuffer.id(),
+                ),
+            }
+        });
+
+        // The storage attached before gets deleted only once it is no longer attached
+        self.attachments.retain(|(a, _)| *a != attachment);
+        self.attachments.push((attachment, storage));
+        self.update_draw_buffers();
+
+        Ok(())
+    }
+
+    // Sends fragment shader output `n` to color attachment `n`, and draws no color at all
+    // without any, as for depth-only passes
+    fn update_draw_buffers(&self) {
+        let count = self.attachments.iter()
+            .filter_map(|(attachment, _)| match attachment {
+                Attachment::Color(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let buffers: Vec<gl::types::GLenum> = (0..count)
+            .map(|index| match self.storage(Attachment::Color(index)) {
+                Some(_) => gl::COLOR_ATTACHMENT0 + index,
+                None => gl::NONE,
+            })
+            .collect();
+
+        // Reads come from the first color attachment, as the default one may not exist
+        let read_buffer = buffers.iter().cloned().find(|&buffer| buffer != gl::NONE).unwrap_or(gl::NONE);
+
+        self.while_bound(|gl| unsafe {
+            if buffers.is_empty() {
+                gl.DrawBuffer(gl::NONE);
+            } else {
+                gl.DrawBuffers(buffers.len() as gl::types::GLsizei, buffers.as_ptr());
+            }
+            gl.ReadBuffer(read_buffer);
+        });
+    }
+
+    // Runs `f` with the framebuffer bound, then binds back whatever was bound before
+    fn while_bound<R>(&self, f: impl FnOnce(&gl::Gl) -> R) -> R {
+        let (mut draw, mut read): (gl::types::GLint, gl::types::GLint) = (0, 0);
+
+        unsafe {
+            self.gl.GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut draw);
+            self.gl.GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut read);
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+        let result = f(&self.gl);
+        unsafe {
+            self.gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, draw as gl::types::GLuint);
+            self.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, read as gl::types::GLuint);
+        }
+
+        result
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteFramebuffers(1, &self.id) };
+    }
+}
@@ -0,0 +1,168 @@
+use crate::render_gl::resources::{self, Resources};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to load resource {}", name)]
+    ResourceLoad {
+        name: String,
+        #[cause] inner: resources::Error,
+    },
+    #[fail(display = "Failed to decode PNG image {}", name)]
+    Decode {
+        name: String,
+        #[cause] inner: png::DecodingError,
+    },
+}
+
+// Single channel image with every pixel as a brightness in [0, 1], stored row by row from the top
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl GrayImage {
+    // Image from brightnesses given row by row from the top, which must fill it exactly
+    pub fn new(width: usize, height: usize, values: Vec<f32>) -> GrayImage {
+        assert_eq!(values.len(), width * height, "GrayImage needs a value for every pixel");
+
+        GrayImage { width, height, values }
+    }
+
+    // Loads a PNG of any color type, keeping the brightness of each pixel. Colors are converted
+    // with the Rec. 709 luma weights and transparent pixels fade to black.
+    pub fn from_res(res: &Resources, name: &str) -> Result<GrayImage, Error> {
+        let (width, height, channels, channel_values) = decode_png(res, name)?;
+
+        let values = channel_values.chunks(channels).map(|c| match channels {
+            1 => c[0],
+            2 => c[0] * c[1],
+            3 => GrayImage::luma(c),
+            _ => GrayImage::luma(c) * c[3],
+        }).collect();
+
+        Ok(GrayImage { width, height, values })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> f32 {
+        self.values[row * self.width + col]
+    }
+
+    // Brightness of the pixel under (u, v) in [0, 1]², where u goes left to right and v top to
+    // bottom. Coordinates outside of the image are clamped to its edges.
+    pub fn sample_nearest(&self, u: f32, v: f32) -> f32 {
+        let col = (u * self.width as f32).floor().max(0.0) as usize;
+        let row = (v * self.height as f32).floor().max(0.0) as usize;
+
+        self.get(col.min(self.width - 1), row.min(self.height - 1))
+    }
+
+    // Mean brightness over the `du` by `dv` box centered on (u, v), in the same coordinates as
+    // `sample_nearest`, weighting every pixel by how much of it the box covers. Boxes get widened
+    // to at least a pixel, which interpolates linearly between neighbouring pixels, and cut off at
+    // the edges of the image.
+    pub fn sample_box(&self, u: f32, v: f32, du: f32, dv: f32) -> f32 {
+        let (w, h) = (self.width as f32, self.height as f32);
+        let half_cols = 0.5 * (du * w).max(1.0);
+        let half_rows = 0.5 * (dv * h).max(1.0);
+        let (col_start, col_end) = ((u * w - half_cols).max(0.0), (u * w + half_cols).min(w));
+        let (row_start, row_end) = ((v * h - half_rows).max(0.0), (v * h + half_rows).min(h));
+
+        if !(col_end > col_start && row_end > row_start) {
+            return self.sample_nearest(u, v);
+        }
+
+        let mut sum = 0.0;
+        for row in row_start.floor() as usize..row_end.ceil() as usize {
+            let row_cover = (row as f32 + 1.0).min(row_end) - (row as f32).max(row_start);
+
+            for col in col_start.floor() as usize..col_end.ceil() as usize {
+                let col_cover = (col as f32 + 1.0).min(col_end) - (col as f32).max(col_start);
+                sum += row_cover * col_cover * self.get(col, row);
+            }
+        }
+
+        sum / ((col_end - col_start) * (row_end - row_start))
+    }
+
+    fn luma(rgb: &[f32]) -> f32 {
+        0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+    }
+}
+
+// Image with every pixel as an RGB color in [0, 1]³, stored row by row from the top
+pub struct RgbImage {
+    width: usize,
+    height: usize,
+    values: Vec<(f32, f32, f32)>,
+}
+
+impl RgbImage {
+    // Loads a PNG of any color type. Gray pixels get the same value on every channel and
+    // transparent pixels fade to black.
+    pub fn from_res(res: &Resources, name: &str) -> Result<RgbImage, Error> {
+        let (width, height, channels, channel_values) = decode_png(res, name)?;
+
+        let values = channel_values.chunks(channels).map(|c| match channels {
+            1 => (c[0], c[0], c[0]),
+            2 => (c[0] * c[1], c[0] * c[1], c[0] * c[1]),
+            3 => (c[0], c[1], c[2]),
+            _ => (c[0] * c[3], c[1] * c[3], c[2] * c[3]),
+        }).collect();
+
+        Ok(RgbImage { width, height, values })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> (f32, f32, f32) {
+        self.values[row * self.width + col]
+    }
+}
+
+// Decodes a PNG into its width, height, number of channels and every channel value in [0, 1],
+// pixel by pixel
+fn decode_png(res: &Resources, name: &str) -> Result<(usize, usize, usize, Vec<f32>), Error> {
+    let bytes = res.load_bytes(name).map_err(|e| Error::ResourceLoad {
+        name: name.into(),
+        inner: e,
+    })?;
+    let decode_error = |e| Error::Decode { name: name.into(), inner: e };
+
+    let mut decoder = png::Decoder::new(&bytes[..]);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder.read_info().map_err(decode_error)?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut buffer).map_err(decode_error)?;
+
+    let (color_type, bit_depth) = reader.output_color_type();
+    let channels = match color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::RGB | png::ColorType::Indexed => 3,
+        png::ColorType::RGBA => 4,
+    };
+    let channel_values = match bit_depth {
+        png::BitDepth::Sixteen => buffer.chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]) as f32 / std::u16::MAX as f32)
+            .collect(),
+        _ => buffer.iter().map(|&c| c as f32 / std::u8::MAX as f32).collect(),
+    };
+
+    Ok((info.width as usize, info.height as usize, channels, channel_values))
+}
@@ -0,0 +1,100 @@
+use gl;
+use nalgebra_glm as glm;
+use crate::render_gl::uniform::{self, Error};
+
+// Most lights of each kind the lit shaders take, matching the sizes of their uniform arrays
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+pub const MAX_POINT_LIGHTS: usize = 4;
+
+// Light coming from infinitely far away, travelling along `direction`
+#[derive(Copy, Clone, Debug)]
+pub struct DirectionalLight {
+    pub direction: glm::Vec3,
+    pub color: glm::Vec3,
+}
+
+// Light spreading out from `position`, fading with distance d as
+// 1 / (attenuation.x + attenuation.y * d + attenuation.z * d²)
+#[derive(Copy, Clone, Debug)]
+pub struct PointLight {
+    pub position: glm::Vec3,
+    pub color: glm::Vec3,
+    pub attenuation: glm::Vec3,
+}
+
+// Blinn-Phong lighting: an ambient term, plus a diffuse and a specular term for every light.
+// Surfaces take their diffuse color from their vertices and have white highlights.
+#[derive(Clone, Debug)]
+pub struct Lighting {
+    pub ambient: glm::Vec3,
+    pub specular: f32,
+    pub shininess: f32,
+    pub directional_lights: Vec<DirectionalLight>,
+    pub point_lights: Vec<PointLight>,
+}
+
+impl Lighting {
+    pub fn new(ambient: glm::Vec3) -> Lighting {
+        Lighting {
+            ambient,
+            specular: 0.5,
+            shininess: 32.0,
+            directional_lights: Vec::new(),
+            point_lights: Vec::new(),
+        }
+    }
+
+    // Lights past the first MAX_DIRECTIONAL_LIGHTS or MAX_POINT_LIGHTS are left out
+    pub fn add_directional_light(&mut self, light: DirectionalLight) {
+        self.directional_lights.push(light);
+    }
+
+    pub fn add_point_light(&mut self, light: PointLight) {
+        self.point_lights.push(light);
+    }
+
+    // Sets the lighting uniforms of the program, which gets used, for a camera at `eye`
+    pub fn apply_uniforms(
+        &self,
+        gl: &gl::Gl,
+        program_id: gl::types::GLuint,
+        eye: glm::Vec3,
+    ) -> Result<(), Error> {
+        let directional_lights = &self.directional_lights[..self.directional_lights.len().min(MAX_DIRECTIONAL_LIGHTS)];
+        let point_lights = &self.point_lights[..self.point_lights.len().min(MAX_POINT_LIGHTS)];
+
+        unsafe {
+            gl.UseProgram(program_id);
+
+            gl.Uniform3fv(uniform::location(gl, program_id, "eye")?, 1, eye.as_ptr());
+            gl.Uniform3fv(uniform::location(gl, program_id, "ambient")?, 1, self.ambient.as_ptr());
+            gl.Uniform1f(uniform::location(gl, program_id, "specular")?, self.specular);
+            gl.Uniform1f(uniform::location(gl, program_id, "shininess")?, self.shininess);
+
+            gl.Uniform1i(
+                uniform::location(gl, program_id, "directional_light_count")?,
+                directional_lights.len() as i32,
+            );
+            for (i, light) in directional_lights.iter().enumerate() {
+                let direction = glm::normalize(&light.direction);
+                let name = |field| format!("directional_lights[{}].{}", i, field);
+
+                gl.Uniform3fv(uniform::location(gl, program_id, &name("direction"))?, 1, direction.as_ptr());
+                gl.Uniform3fv(uniform::location(gl, program_id, &name("color"))?, 1, light.color.as_ptr());
+            }
+
+            gl.Uniform1i(uniform::location(gl, program_id, "point_light_count")?, point_lights.len() as i32);
+            for (i, light) in point_lights.iter().enumerate() {
+                let name = |field| format!("point_lights[{}].{}", i, field);
+
+                gl.Uniform3fv(uniform::location(gl, program_id, &name("position"))?, 1, light.position.as_ptr());
+                gl.Uniform3fv(uniform::location(gl, program_id, &name("color"))?, 1, light.color.as_ptr());
+                gl.Uniform3fv(
+                    uniform::location(gl, program_id, &name("attenuation"))?, 1, light.attenuation.as_ptr(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -7,6 +7,13 @@ pub use self::viewport::Viewport;
 pub use self::color_buffer::ColorBuffer;
 
 pub mod data;
+pub mod colormap;
+pub mod image;
+pub mod lighting;
 pub mod buffer;
+pub mod framebuffer;
+pub mod transform;
 pub mod uniform;
 pub mod resources;
+pub mod scene;
+pub mod texture;
@@ -0,0 +1,199 @@
+use std::any::Any;
+
+use gl;
+use failure;
+use nalgebra_glm as glm;
+use crate::render_gl::transform::Transform;
+use crate::render_gl::Viewport;
+
+// Lets the scene hand its objects back as their concrete types
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Something the scene can draw
+pub trait Renderable: AsAny {
+    // Program that takes the view and projection uniforms of the camera, or `None` for objects
+    // drawn straight in screen space
+    fn camera_program(&self) -> Option<gl::types::GLuint>;
+
+    // Draws the object, placed in the world by `model` on top of any transform of its own
+    fn render(&mut self, gl: &gl::Gl, model: &glm::Mat4) -> Result<(), failure::Error>;
+}
+
+pub type NodeId = usize;
+
+// Named point of the scene, placed relative to its parent, which may hold an object to draw
+pub struct Node {
+    pub transform: Transform,
+    // Hidden nodes are skipped together with all of their children
+    pub visible: bool,
+    name: String,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    object: Option<Box<dyn Renderable>>,
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+// Tree of nodes, drawn parents first. Nodes are never removed, so their ids stay valid.
+pub struct Scene {
+    nodes: Vec<Node>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene { nodes: Vec::new() }
+    }
+
+    // Adds an empty node, under `parent` or at the top of the scene. Gives `None`, adding
+    // nothing, when there is no node `parent`.
+    pub fn add_node(&mut self, name: &str, parent: Option<NodeId>) -> Option<NodeId> {
+        let id = self.nodes.len();
+
+        if let Some(parent) = parent {
+            self.nodes.get_mut(parent)?.children.push(id);
+        }
+        self.nodes.push(Node {
+            transform: Transform::identity(),
+            visible: true,
+            name: name.into(),
+            parent,
+            children: Vec::new(),
+            object: None,
+        });
+
+        Some(id)
+    }
+
+    pub fn add_object(
+        &mut self,
+        name: &str,
+        object: impl Renderable + 'static,
+        parent: Option<NodeId>,
+    ) -> Option<NodeId> {
+        let id = self.add_node(name, parent)?;
+        self.nodes[id].object = Some(Box::new(object));
+
+        Some(id)
+    }
+
+    // First node with the name, in the order they were added
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id)
+    }
+
+    // Object of the node, if it holds one of type T
+    pub fn object<T: Renderable + 'static>(&self, id: NodeId) -> Option<&T> {
+        let object = self.nodes.get(id)?.object.as_ref()?;
+
+        (**object).as_any().downcast_ref::<T>()
+    }
+
+    pub fn object_mut<T: Renderable + 'static>(&mut self, id: NodeId) -> Option<&mut T> {
+        let object = self.nodes.get_mut(id)?.object.as_mut()?;
+
+        (**object).as_any_mut().downcast_mut::<T>()
+    }
+
+    // Object of the first node with the name, if it is of type T
+    pub fn find_object<T: Renderable + 'static>(&self, name: &str) -> Option<&T> {
+        let id = self.find(name)?;
+
+        self.object(id)
+    }
+
+    pub fn find_object_mut<T: Renderable + 'static>(&mut self, name: &str) -> Option<&mut T> {
+        let id = self.find(name)?;
+
+        self.object_mut(id)
+    }
+
+    // Transform from the node's coordinates to the world's, through all of its parents
+    pub fn world_matrix(&self, id: NodeId) -> glm::Mat4 {
+        let mut matrix = glm::Mat4::identity();
+        let mut current = Some(id);
+
+        while let Some(node) = current.and_then(|id| self.nodes.get(id)) {
+            matrix = node.transform.matrix() * matrix;
+            current = node.parent;
+        }
+
+        matrix
+    }
+
+    // Draws every visible object, applying the camera of the viewport to those that use one
+    pub fn render(&mut self, gl: &gl::Gl, viewport: &mut Viewport) -> Result<(), failure::Error> {
+        let roots: Vec<NodeId> = (0..self.nodes.len()).filter(|&id| self.nodes[id].parent.is_none()).collect();
+
+        for root in roots {
+            self.render_node(gl, viewport, root, &glm::Mat4::identity())?;
+        }
+
+        Ok(())
+    }
+
+    fn render_node(
+        &mut self,
+        gl: &gl::Gl,
+        viewport: &mut Viewport,
+        id: NodeId,
+        parent_matrix: &glm::Mat4,
+    ) -> Result<(), failure::Error> {
+        let node = &mut self.nodes[id];
+        if !node.visible {
+            return Ok(());
+        }
+
+        let matrix = parent_matrix * node.transform.matrix();
+        if let Some(object) = node.object.as_mut() {
+            if let Some(program_id) = object.camera_program() {
+                viewport.apply_uniforms(program_id)?;
+            }
+            object.render(gl, &matrix)?;
+        }
+
+        for child in node.children.clone() {
+            self.render_node(gl, viewport, child, &matrix)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
+}
@@ -0,0 +1,196 @@
+use gl;
+
+// Layout of the texels of a texture or renderbuffer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFormat {
+    Rgba8,
+    Rgba32F,
+    // Single float channel, e.g. for heights or object ids to pick from
+    R32F,
+    Depth24,
+    Depth24Stencil8,
+}
+
+impl TextureFormat {
+    pub(super) fn internal_format(self) -> gl::types::GLenum {
+        match self {
+            TextureFormat::Rgba8 => gl::RGBA8,
+            TextureFormat::Rgba32F => gl::RGBA32F,
+            TextureFormat::R32F => gl::R32F,
+            TextureFormat::Depth24 => gl::DEPTH_COMPONENT24,
+            TextureFormat::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+        }
+    }
+
+    // Format and type of client-side pixel data matching the texels
+    fn pixel_format(self) -> (gl::types::GLenum, gl::types::GLenum) {
+        match self {
+            TextureFormat::Rgba8 => (gl::RGBA, gl::UNSIGNED_BYTE),
+            TextureFormat::Rgba32F => (gl::RGBA, gl::FLOAT),
+            TextureFormat::R32F => (gl::RED, gl::FLOAT),
+            TextureFormat::Depth24 => (gl::DEPTH_COMPONENT, gl::UNSIGNED_INT),
+            TextureFormat::Depth24Stencil8 => (gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+        }
+    }
+
+    // Size of a texel in bytes
+    fn texel_size(self) -> usize {
+        match self {
+            TextureFormat::Rgba32F => 16,
+            _ => 4,
+        }
+    }
+
+    pub fn is_depth(self) -> bool {
+        match self {
+            TextureFormat::Depth24 | TextureFormat::Depth24Stencil8 => true,
+            _ => false,
+        }
+    }
+
+    pub fn has_stencil(self) -> bool {
+        self == TextureFormat::Depth24Stencil8
+    }
+}
+
+// How a texture gets sampled between and across texels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+// Two-dimensional texture with uninitialized storage, clamped at its edges
+pub struct Texture2D {
+    gl: gl::Gl,
+    id: gl::types::GLuint,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+}
+
+impl Texture2D {
+    pub fn new(gl: &gl::Gl, width: u32, height: u32, format: TextureFormat) -> Texture2D {
+        let mut id: gl::types::GLuint = 0;
+        unsafe { gl.GenTextures(1, &mut id) };
+
+        let mut texture = Texture2D { gl: gl.clone(), id, width: 0, height: 0, format };
+        texture.resize(width, height);
+        texture.set_filter(Filter::Linear);
+
+        unsafe {
+            gl.BindTexture(gl::TEXTURE_2D, id);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as gl::types::GLint);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as gl::types::GLint);
+            gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        texture
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    // Binds the texture to texture unit `unit`, for samplers set to that unit to read
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+
+    pub fn unbind(&self, unit: u32) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        let filter = match filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        } as gl::types::GLint;
+
+        unsafe {
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+            self.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter);
+            self.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter);
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    // Replaces the storage with uninitialized storage of the new size, discarding the contents
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let (format, data_type) = self.format.pixel_format();
+        self.width = width;
+        self.height = height;
+
+        unsafe {
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+            self.gl.TexImage2D(
+                gl::TEXTURE_2D,  // target
+                0,  // mipmap level
+                self.format.internal_format() as gl::types::GLint,  // format of the texels
+                width as gl::types::GLsizei,
+                height as gl::types::GLsizei,
+                0,  // border, always 0
+                format,  // format of the pixel data
+                data_type,  // type of the pixel data
+                ::std::ptr::null(),  // no data to copy
+            );
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    // Overwrites every texel, a row at a time from the bottom up, with `data` laid out as the
+    // texels are, e.g. four floats per texel of an Rgba32F texture
+    pub fn set_data<T: Copy>(&mut self, data: &[T]) {
+        let (format, data_type) = self.format.pixel_format();
+        let size = (self.width as usize)
+            .checked_mul(self.height as usize)
+            .and_then(|texels| texels.checked_mul(self.format.texel_size()));
+        assert_eq!(
+            size, Some(data.len() * ::std::mem::size_of::<T>()),
+            "data does not match the size of the {}x{} texture", self.width, self.height,
+        );
+
+        unsafe {
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+            // Rows are packed tightly, whatever their length
+            self.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            self.gl.TexSubImage2D(
+                gl::TEXTURE_2D,  // target
+                0,  // mipmap level
+                0,  // x offset
+                0,  // y offset
+                self.width as gl::types::GLsizei,
+                self.height as gl::types::GLsizei,
+                format,  // format of the pixel data
+                data_type,  // type of the pixel data
+                data.as_ptr() as *const gl::types::GLvoid,  // pointer to data
+            );
+            self.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteTextures(1, &self.id) };
+    }
+}
@@ -0,0 +1,43 @@
+use nalgebra_glm as glm;
+
+// Placement of an object in the world: scaled along its own axes first, then rotated, then moved
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: glm::Vec3,
+    pub rotation: glm::Quat,
+    pub scale: glm::Vec3,
+}
+
+impl Transform {
+    pub fn new(translation: glm::Vec3, rotation: glm::Quat, scale: glm::Vec3) -> Transform {
+        Transform { translation, rotation, scale }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(glm::vec3(0.0, 0.0, 0.0), glm::quat_identity(), glm::vec3(1.0, 1.0, 1.0))
+    }
+
+    pub fn from_translation(translation: glm::Vec3) -> Transform {
+        Transform { translation, ..Transform::identity() }
+    }
+
+    pub fn from_scale(scale: glm::Vec3) -> Transform {
+        Transform { scale, ..Transform::identity() }
+    }
+
+    // Turns by `angle` radians around `axis`, on top of the current rotation
+    pub fn rotate(&mut self, angle: f32, axis: &glm::Vec3) {
+        self.rotation = glm::quat_normalize(&(glm::quat_angle_axis(angle, axis) * self.rotation));
+    }
+
+    // Model matrix taking object coordinates to world coordinates
+    pub fn matrix(&self) -> glm::Mat4 {
+        glm::translation(&self.translation) * glm::quat_to_mat4(&self.rotation) * glm::scaling(&self.scale)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
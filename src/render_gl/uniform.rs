@@ -1,5 +1,6 @@
 use gl;
 use nalgebra_glm as glm;
+use std::ffi::CString;
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -11,6 +12,18 @@ pub trait Uniform {
     fn apply_uniform(&mut self, program_id: gl::types::GLuint) -> Result<(), Error>;
 }
 
+// Location of the active uniform called `name` in the program
+pub fn location(gl: &gl::Gl, program_id: gl::types::GLuint, name: &str) -> Result<i32, Error> {
+    let c_name = CString::new(name).map_err(|_| Error::UniformNotFound { name: name.to_string() })?;
+    let location = unsafe { gl.GetUniformLocation(program_id, c_name.as_ptr()) };
+
+    if location < 0 {
+        return Err(Error::UniformNotFound { name: name.to_string() });
+    }
+
+    Ok(location)
+}
+
 
 pub struct UniformFMat4 {
     name: String,
@@ -31,6 +44,10 @@ impl UniformFMat4 {
     pub fn update(&mut self, data: &glm::Mat4) {
         self.data = data.clone();
     }
+
+    pub fn data(&self) -> &glm::Mat4 {
+        &self.data
+    }
 }
 
 impl Uniform for UniformFMat4 {
@@ -85,6 +85,38 @@ impl Viewport {
         self.view_uniform.update(&glm::look_at(&self.position, &self.center, &self.up));
     }
 
+    // World space ray (origin, unit direction) through the window pixel (x, y)
+    pub fn screen_ray(&self, x: i32, y: i32) -> (glm::Vec3, glm::Vec3) {
+        let ndc_x = 2.0 * (x - self.x) as f32 / self.w as f32 - 1.0;
+        let ndc_y = 1.0 - 2.0 * (y - self.y) as f32 / self.h as f32;
+
+        let inverse = glm::inverse(&(self.proj_uniform.data() * self.view_uniform.data()));
+        let near = inverse * glm::vec4(ndc_x, ndc_y, -1.0, 1.0);
+        let far = inverse * glm::vec4(ndc_x, ndc_y, 1.0, 1.0);
+
+        let near = glm::vec3(near.x, near.y, near.z) / near.w;
+        let far = glm::vec3(far.x, far.y, far.z) / far.w;
+
+        (near, glm::normalize(&(far - near)))
+    }
+
+    // Point where the ray through the window pixel (x, y) hits the horizontal plane at the given
+    // height, if it does so in front of the camera
+    pub fn pick_plane(&self, x: i32, y: i32, height: f32) -> Option<glm::Vec3> {
+        let (origin, direction) = self.screen_ray(x, y);
+
+        if direction.y.abs() < std::f32::EPSILON {
+            return None;
+        }
+
+        let t = (height - origin.y) / direction.y;
+        if t < 0.0 {
+            return None;
+        }
+
+        Some(origin + direction * t)
+    }
+
     pub fn apply_uniforms(&mut self, program_id: gl::types::GLuint) -> Result<(), Error> {
         self.view_uniform.apply_uniform(program_id).map_err(
             |e| Error::ResourceLoad { inner: e }
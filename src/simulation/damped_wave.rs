@@ -0,0 +1,95 @@
+use super::FieldSimulation;
+use super::lattice::{Edges, Lattice};
+
+// Telegraph equation u_tt + damping * u_t = wave_speed² * ∇²u with the edges held at zero,
+// integrated with the classic three level leapfrog scheme on the values alone
+pub struct DampedWave {
+    lattice: Lattice,
+    wave_speed: f32,
+    damping: f32,
+    vals: Vec<f32>,
+    prev_vals: Vec<f32>,
+    next_vals: Vec<f32>,
+    // Time step `prev_vals` was taken with, or None before the first step
+    prev_dt: Option<f32>,
+}
+
+impl DampedWave {
+    pub fn new(
+        granularity: i32, wave_speed: f32, damping: f32, start_func: impl Fn(f32, f32) -> f32
+    ) -> DampedWave {
+        let lattice = Lattice::new(granularity, Edges::Zero);
+        let vals = lattice.sample_func(start_func);
+
+        DampedWave {
+            lattice,
+            wave_speed,
+            damping,
+            prev_vals: vals.clone(),
+            next_vals: vals.clone(),
+            vals,
+            prev_dt: None,
+        }
+    }
+
+    fn max_stable_dt(&self) -> f32 {
+        std::f32::consts::FRAC_1_SQRT_2 * self.lattice.step() / self.wave_speed
+    }
+
+    fn substep(&mut self, dt: f32) {
+        // The scheme assumes a constant step. When it changes, the previous level is moved so
+        // that it implies the same velocity over the new step.
+        match self.prev_dt {
+            Some(prev_dt) if prev_dt != dt => {
+                let scale = dt / prev_dt;
+                for (prev, val) in self.prev_vals.iter_mut().zip(self.vals.iter()) {
+                    *prev = val - (val - *prev) * scale;
+                }
+            },
+            _ => {},
+        }
+        self.prev_dt = Some(dt);
+
+        let c2_dt2 = self.wave_speed * self.wave_speed * dt * dt;
+        let half_damping = 0.5 * self.damping * dt;
+
+        for i in 0..self.lattice.len() {
+            if self.lattice.is_active(i) {
+                let laplacian = self.lattice.laplacian(&self.vals, i);
+                self.next_vals[i] = (2.0 * self.vals[i] - (1.0 - half_damping) * self.prev_vals[i]
+                    + c2_dt2 * laplacian) / (1.0 + half_damping);
+            }
+        }
+
+        // Rotate prev <- current <- next, reusing the oldest buffer for the next step
+        std::mem::swap(&mut self.prev_vals, &mut self.vals);
+        std::mem::swap(&mut self.vals, &mut self.next_vals);
+    }
+}
+
+impl FieldSimulation for DampedWave {
+    fn step(&mut self, dt: f32) -> Result<(), failure::Error> {
+        let substeps = Lattice::substeps(dt, self.max_stable_dt());
+        let sub_dt = dt / substeps as f32;
+
+        for _ in 0..substeps {
+            self.substep(sub_dt);
+        }
+
+        Ok(())
+    }
+
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.lattice.sample(&self.vals, x, y)
+    }
+
+    fn resolution(&self) -> (usize, usize) {
+        (self.lattice.size, self.lattice.size)
+    }
+
+    fn add_drop(&mut self, x: f32, y: f32, amplitude: f32, radius: f32) {
+        // Raising both levels adds the bump without giving it any velocity
+        self.lattice.add_gaussian(&mut self.vals, x, y, amplitude, radius);
+        self.lattice.add_gaussian(&mut self.prev_vals, x, y, amplitude, radius);
+    }
+}
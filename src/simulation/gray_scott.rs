@@ -0,0 +1,97 @@
+use super::FieldSimulation;
+use super::lattice::{Edges, Lattice};
+
+// Gray-Scott reaction-diffusion on a periodic domain, where u is fed in, v consumes it and both
+// diffuse:
+//   u_t = diffusion_u * ∇²u - u v² + feed * (1 - u)
+//   v_t = diffusion_v * ∇²v + u v² - (feed + kill) * v
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GrayScottParams {
+    pub diffusion_u: f32,
+    pub diffusion_v: f32,
+    pub feed: f32,
+    pub kill: f32,
+}
+
+impl GrayScottParams {
+    // The usual "mitosis" parameters, with the diffusion rates given per lattice cell so the
+    // patterns keep the same size in cells whatever the granularity
+    pub fn mitosis(granularity: i32) -> GrayScottParams {
+        let step = 1.0 / granularity as f32;
+
+        GrayScottParams {
+            diffusion_u: 0.16 * step * step,
+            diffusion_v: 0.08 * step * step,
+            feed: 0.0367,
+            kill: 0.0649,
+        }
+    }
+}
+
+pub struct GrayScott {
+    lattice: Lattice,
+    params: GrayScottParams,
+    u: Vec<f32>,
+    v: Vec<f32>,
+    next_u: Vec<f32>,
+    next_v: Vec<f32>,
+}
+
+impl GrayScott {
+    // `seed_func` gives the initial concentration of v, with u depleted where v is present
+    pub fn new(
+        granularity: i32, params: GrayScottParams, seed_func: impl Fn(f32, f32) -> f32
+    ) -> GrayScott {
+        let lattice = Lattice::new(granularity, Edges::Periodic);
+        let v = lattice.sample_func(seed_func);
+        let u: Vec<f32> = v.iter().map(|v| 1.0 - 2.0 * v).collect();
+
+        GrayScott { lattice, params, next_u: u.clone(), next_v: v.clone(), u, v }
+    }
+
+    fn max_stable_dt(&self) -> f32 {
+        let step = self.lattice.step();
+        let diffusion = self.params.diffusion_u.max(self.params.diffusion_v);
+
+        // Diffusion limit of forward Euler, also capped so the reaction terms stay accurate
+        (0.25 * step * step / diffusion).min(1.0)
+    }
+}
+
+impl FieldSimulation for GrayScott {
+    fn step(&mut self, dt: f32) -> Result<(), failure::Error> {
+        let p = self.params;
+        let substeps = Lattice::substeps(dt, self.max_stable_dt());
+        let sub_dt = dt / substeps as f32;
+
+        for _ in 0..substeps {
+            for i in 0..self.lattice.len() {
+                let (u, v) = (self.u[i], self.v[i]);
+                let reaction = u * v * v;
+
+                let du = p.diffusion_u * self.lattice.laplacian(&self.u, i) - reaction + p.feed * (1.0 - u);
+                let dv = p.diffusion_v * self.lattice.laplacian(&self.v, i) + reaction - (p.feed + p.kill) * v;
+
+                self.next_u[i] = u + du * sub_dt;
+                self.next_v[i] = v + dv * sub_dt;
+            }
+
+            std::mem::swap(&mut self.u, &mut self.next_u);
+            std::mem::swap(&mut self.v, &mut self.next_v);
+        }
+
+        Ok(())
+    }
+
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.lattice.sample(&self.v, x, y)
+    }
+
+    fn resolution(&self) -> (usize, usize) {
+        (self.lattice.size, self.lattice.size)
+    }
+
+    fn add_drop(&mut self, x: f32, y: f32, amplitude: f32, radius: f32) {
+        self.lattice.add_gaussian(&mut self.v, x, y, amplitude, radius);
+    }
+}
@@ -0,0 +1,57 @@
+use super::FieldSimulation;
+use super::lattice::{Edges, Lattice};
+
+// Heat equation u_t = diffusivity * ∇²u with the edges held at zero
+pub struct Heat {
+    lattice: Lattice,
+    diffusivity: f32,
+    vals: Vec<f32>,
+    next_vals: Vec<f32>,
+}
+
+impl Heat {
+    pub fn new(granularity: i32, diffusivity: f32, start_func: impl Fn(f32, f32) -> f32) -> Heat {
+        let lattice = Lattice::new(granularity, Edges::Zero);
+        let vals = lattice.sample_func(start_func);
+
+        Heat { lattice, diffusivity, next_vals: vals.clone(), vals }
+    }
+
+    // Forward Euler is stable while diffusivity * dt / step² stays below 1/4
+    fn max_stable_dt(&self) -> f32 {
+        let step = self.lattice.step();
+        0.25 * step * step / self.diffusivity
+    }
+}
+
+impl FieldSimulation for Heat {
+    fn step(&mut self, dt: f32) -> Result<(), failure::Error> {
+        let substeps = Lattice::substeps(dt, self.max_stable_dt());
+        let sub_dt = dt / substeps as f32;
+
+        for _ in 0..substeps {
+            for i in 0..self.lattice.len() {
+                if self.lattice.is_active(i) {
+                    let laplacian = self.lattice.laplacian(&self.vals, i);
+                    self.next_vals[i] = self.vals[i] + self.diffusivity * laplacian * sub_dt;
+                }
+            }
+
+            std::mem::swap(&mut self.vals, &mut self.next_vals);
+        }
+
+        Ok(())
+    }
+
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.lattice.sample(&self.vals, x, y)
+    }
+
+    fn resolution(&self) -> (usize, usize) {
+        (self.lattice.size, self.lattice.size)
+    }
+
+    fn add_drop(&mut self, x: f32, y: f32, amplitude: f32, radius: f32) {
+        self.lattice.add_gaussian(&mut self.vals, x, y, amplitude, radius);
+    }
+}
@@ -0,0 +1,144 @@
+// Square lattice of (2 * granularity + 1)² points over [-1, 1]², stored row-major with one row
+// per x coordinate, shared by the simpler field solvers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lattice {
+    pub granularity: i32,
+    pub size: usize,
+    pub edges: Edges,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Edges {
+    // Edge points are held at zero
+    Zero,
+    // The domain wraps around on both axes
+    Periodic,
+}
+
+impl Lattice {
+    pub fn new(granularity: i32, edges: Edges) -> Lattice {
+        Lattice { granularity, size: (2 * granularity + 1) as usize, edges }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size * self.size
+    }
+
+    pub fn step(&self) -> f32 {
+        1.0 / self.granularity as f32
+    }
+
+    pub fn sample_func(&self, f: impl Fn(f32, f32) -> f32) -> Vec<f32> {
+        let mut vals = vec![0.0; self.len()];
+
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let x_f = (row as i32 - self.granularity) as f32 / self.granularity as f32;
+                let y_f = (col as i32 - self.granularity) as f32 / self.granularity as f32;
+
+                vals[row * self.size + col] = f(x_f, y_f);
+            }
+        }
+
+        self.apply_edges(&mut vals);
+        vals
+    }
+
+    // Whether the point at `i` is updated by the solvers, rather than held by the edges
+    pub fn is_active(&self, i: usize) -> bool {
+        if self.edges == Edges::Periodic {
+            return true;
+        }
+
+        let (row, col) = (i / self.size, i % self.size);
+        row > 0 && col > 0 && row < self.size - 1 && col < self.size - 1
+    }
+
+    pub fn laplacian(&self, u: &[f32], i: usize) -> f32 {
+        let size = self.size;
+        let (row, col) = (i / size, i % size);
+        // On a periodic lattice the last row and column repeat the first ones
+        let wrap = |k: usize, delta: isize| -> usize {
+            let period = size as isize - 1;
+            ((k as isize + delta + period) % period) as usize
+        };
+
+        let (up, down, left, right) = match self.edges {
+            Edges::Periodic => (
+                wrap(row, 1) * size + col,
+                wrap(row, -1) * size + col,
+                row * size + wrap(col, -1),
+                row * size + wrap(col, 1),
+            ),
+            Edges::Zero => (i + size, i - size, i - 1, i + 1),
+        };
+
+        let step = self.step();
+        (u[up] + u[down] + u[left] + u[right] - 4.0 * u[i]) / (step * step)
+    }
+
+    pub fn apply_edges(&self, vals: &mut [f32]) {
+        let size = self.size;
+        let last = size - 1;
+
+        match self.edges {
+            Edges::Zero => {
+                for k in 0..size {
+                    vals[k] = 0.0;
+                    vals[last * size + k] = 0.0;
+                    vals[k * size] = 0.0;
+                    vals[k * size + last] = 0.0;
+                }
+            },
+            Edges::Periodic => {
+                for k in 0..size {
+                    vals[last * size + k] = vals[k];
+                }
+                for k in 0..size {
+                    vals[k * size + last] = vals[k * size];
+                }
+            },
+        }
+    }
+
+    // Bilinear interpolation of `vals` at (x, y). Points outside of the domain read as 0.
+    pub fn sample(&self, vals: &[f32], x: f32, y: f32) -> f32 {
+        if x < -1.0 || x > 1.0 || y < -1.0 || y > 1.0 {
+            return 0.0;
+        }
+
+        let last = self.size - 1;
+        let row_f = (x + 1.0) * self.granularity as f32;
+        let col_f = (y + 1.0) * self.granularity as f32;
+        let row = (row_f.floor() as usize).min(last - 1);
+        let col = (col_f.floor() as usize).min(last - 1);
+        let tx = row_f - row as f32;
+        let ty = col_f - col as f32;
+
+        let at = |r: usize, c: usize| vals[r * self.size + c];
+        let near = at(row, col) + (at(row, col + 1) - at(row, col)) * ty;
+        let far = at(row + 1, col) + (at(row + 1, col + 1) - at(row + 1, col)) * ty;
+
+        near + (far - near) * tx
+    }
+
+    // Adds a gaussian bump of the given height and standard deviation centered at (x, y)
+    pub fn add_gaussian(&self, vals: &mut [f32], x: f32, y: f32, amplitude: f32, radius: f32) {
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let dx = (row as i32 - self.granularity) as f32 * self.step() - x;
+                let dy = (col as i32 - self.granularity) as f32 * self.step() - y;
+
+                vals[row * self.size + col] +=
+                    amplitude * (-(dx * dx + dy * dy) / (2.0 * radius * radius)).exp();
+            }
+        }
+
+        self.apply_edges(vals);
+    }
+
+    // Number of equal sub-steps `dt` must be split into so none is longer than `max_dt`
+    pub fn substeps(dt: f32, max_dt: f32) -> u32 {
+        ((dt / max_dt).ceil() as u32).max(1)
+    }
+}
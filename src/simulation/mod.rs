@@ -0,0 +1,113 @@
+mod damped_wave;
+mod gray_scott;
+mod heat;
+mod lattice;
+
+pub use self::damped_wave::DampedWave;
+pub use self::gray_scott::{GrayScott, GrayScottParams};
+pub use self::heat::Heat;
+
+use std::fs::File;
+use std::path::Path;
+
+use crate::wave_estimator::{Diagnostics, WaveEstimator};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Simulation does not support checkpoints")]
+    CheckpointsUnsupported,
+}
+
+// A scalar field over a rectangle centered on the origin, [-1, 1]² unless built otherwise,
+// that evolves in time
+pub trait FieldSimulation {
+    fn step(&mut self, dt: f32) -> Result<(), failure::Error>;
+    // Value of the field at (x, y). Points outside of the domain read as 0.
+    fn sample(&self, x: f32, y: f32) -> f32;
+    // Number of lattice points along the x and y axes
+    fn resolution(&self) -> (usize, usize);
+
+    // Disturbs the field with a gaussian bump centered at (x, y)
+    fn add_drop(&mut self, _x: f32, _y: f32, _amplitude: f32, _radius: f32) {}
+
+    // Whether the point closest to (x, y) is an obstacle
+    fn is_solid(&self, _x: f32, _y: f32) -> bool {
+        false
+    }
+
+    // Energy and amplitude readout, for simulations that track it
+    fn diagnostics(&self) -> Option<Diagnostics> {
+        None
+    }
+
+    // Restores the recorded state `offset` snapshots before (negative) or after (positive) the
+    // one shown, returning false for simulations that keep no history. Stepping the simulation
+    // afterwards resumes from the restored state.
+    fn scrub(&mut self, _offset: isize) -> bool {
+        false
+    }
+
+    // Writes the full state to `path`, so that `load_checkpoint` can later resume from it
+    fn save_checkpoint(&self, _path: &Path) -> Result<(), failure::Error> {
+        Err(Error::CheckpointsUnsupported.into())
+    }
+
+    fn load_checkpoint(&mut self, _path: &Path) -> Result<(), failure::Error> {
+        Err(Error::CheckpointsUnsupported.into())
+    }
+}
+
+impl FieldSimulation for WaveEstimator {
+    fn step(&mut self, dt: f32) -> Result<(), failure::Error> {
+        self.update(dt)?;
+        Ok(())
+    }
+
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.get_val(x, y)
+    }
+
+    fn resolution(&self) -> (usize, usize) {
+        self.resolution()
+    }
+
+    fn add_drop(&mut self, x: f32, y: f32, amplitude: f32, radius: f32) {
+        WaveEstimator::add_drop(self, x, y, amplitude, radius);
+    }
+
+    fn is_solid(&self, x: f32, y: f32) -> bool {
+        WaveEstimator::is_solid(self, x, y)
+    }
+
+    fn diagnostics(&self) -> Option<Diagnostics> {
+        Some(WaveEstimator::diagnostics(self))
+    }
+
+    fn scrub(&mut self, offset: isize) -> bool {
+        WaveEstimator::scrub(self, offset).is_some()
+    }
+
+    // Also exports the state next to the checkpoint for analysis, whole as an .npz archive and
+    // the values alone as an .npy array
+    fn save_checkpoint(&self, path: &Path) -> Result<(), failure::Error> {
+        self.save_to_file(path)?;
+        self.save_npz(File::create(path.with_extension("npz"))?)?;
+        self.save_npy(File::create(path.with_extension("npy"))?)?;
+        Ok(())
+    }
+
+    fn load_checkpoint(&mut self, path: &Path) -> Result<(), failure::Error> {
+        let mut estimator = WaveEstimator::load_from_file(path)?;
+        // How the update runs and where it logs to are not part of the simulation state
+        #[cfg(feature = "parallel")]
+        estimator.set_parallel(self.parallel());
+        estimator.set_diagnostics_log(self.take_diagnostics_log());
+        // The recorded history belongs to the state being replaced, so it starts over
+        if let Some(history) = self.history() {
+            estimator.enable_history(history.capacity(), history.interval());
+        }
+
+        *self = estimator;
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 use gl;
 use failure;
+use nalgebra_glm as glm;
 use crate::render_gl::{self, data, buffer};
 use crate::render_gl::resources::Resources;
+use crate::render_gl::scene::Renderable;
+use crate::render_gl::transform::Transform;
+use crate::render_gl::uniform::{Uniform, UniformFMat4};
 
 #[derive(VertexAttribPointers)]
 #[derive(Copy, Clone, Debug)]
@@ -17,6 +21,8 @@ pub struct Triangle {
     program: render_gl::Program,
     _vbo: buffer::ArrayBuffer,
     vao: buffer::VertexArray,
+    transform: Transform,
+    model_uniform: UniformFMat4,
 }
 
 impl Triangle {
@@ -67,7 +73,13 @@ impl Triangle {
         vbo.unbind();
         vao.unbind();
 
-        Ok(Triangle{program, _vbo: vbo, vao})
+        Ok(Triangle{
+            program,
+            _vbo: vbo,
+            vao,
+            transform: Transform::identity(),
+            model_uniform: UniformFMat4::new_with_loc(gl, "model", glm::Mat4::identity(), 0),
+        })
 
 
     }
@@ -76,8 +88,24 @@ impl Triangle {
         self.program.id()
     }
 
-    pub fn render(&self, gl: &gl::Gl) {
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+}
+
+impl Renderable for Triangle {
+    fn camera_program(&self) -> Option<gl::types::GLuint> {
+        Some(self.program.id())
+    }
+
+    fn render(&mut self, gl: &gl::Gl, model: &glm::Mat4) -> Result<(), failure::Error> {
         self.program.set_used();
+        self.model_uniform.update(&(model * self.transform.matrix()));
+        self.model_uniform.apply_uniform(self.program.id())?;
         self.vao.bind();
 
         unsafe {
@@ -87,5 +115,7 @@ impl Triangle {
                 6,  // number of indices to be rendered
             );
         }
+
+        Ok(())
     }
 }
\ No newline at end of file
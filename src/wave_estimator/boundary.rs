@@ -0,0 +1,130 @@
+use super::{Error, WaveEstimator};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoundaryCondition {
+    // Dirichlet: the edge is clamped to the given value
+    Fixed(f32),
+    // Neumann: zero slope across the edge, so waves reflect off it like a free end
+    Free,
+    // The edge wraps around onto the opposite one
+    Periodic,
+    // First order Mur radiation condition, which lets waves leave the domain
+    Absorbing,
+}
+
+impl BoundaryCondition {
+    // Whether cells on an edge with this condition are integrated like interior cells
+    pub(super) fn is_dynamic(&self) -> bool {
+        match self {
+            BoundaryCondition::Free | BoundaryCondition::Periodic => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Boundaries {
+    pub x_min: BoundaryCondition,
+    pub x_max: BoundaryCondition,
+    pub y_min: BoundaryCondition,
+    pub y_max: BoundaryCondition,
+}
+
+impl Boundaries {
+    pub fn uniform(condition: BoundaryCondition) -> Boundaries {
+        Boundaries { x_min: condition, x_max: condition, y_min: condition, y_max: condition }
+    }
+
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        if (self.x_min == BoundaryCondition::Periodic) != (self.x_max == BoundaryCondition::Periodic) {
+            return Err(Error::UnpairedPeriodicBoundary { axis: "x".to_string() });
+        }
+        if (self.y_min == BoundaryCondition::Periodic) != (self.y_max == BoundaryCondition::Periodic) {
+            return Err(Error::UnpairedPeriodicBoundary { axis: "y".to_string() });
+        }
+        Ok(())
+    }
+
+    // Each side as (condition, first cell, stride along the edge, number of cells, offset to the
+    // inner cell) on a row-major lattice of `rows` by `cols` points. x sides come first, then y
+    // sides.
+    pub(super) fn sides(&self, rows: usize, cols: usize) -> [(BoundaryCondition, usize, usize, usize, isize); 4] {
+        [
+            (self.x_min, 0, 1, cols, cols as isize),
+            (self.x_max, (rows - 1) * cols, 1, cols, -(cols as isize)),
+            (self.y_min, 0, cols, rows, 1),
+            (self.y_max, cols - 1, cols, rows, -1),
+        ]
+    }
+
+    // Clamps the cells of every fixed side in `vals` to their value
+    pub(super) fn apply_fixed(&self, rows: usize, cols: usize, vals: &mut [f32]) {
+        for &(condition, start, along, len, _) in self.sides(rows, cols).iter() {
+            if let BoundaryCondition::Fixed(v) = condition {
+                for k in 0..len {
+                    vals[start + k * along] = v;
+                }
+            }
+        }
+    }
+
+    // Both ends of a periodic axis are the same physical point, so the last row (or column)
+    // mirrors the first one
+    pub(super) fn sync_periodic(&self, rows: usize, cols: usize, buf: &mut [f32]) {
+        if self.x_min == BoundaryCondition::Periodic {
+            for col in 0..cols {
+                buf[(rows - 1) * cols + col] = buf[col];
+            }
+        }
+        if self.y_min == BoundaryCondition::Periodic {
+            for row in 0..rows {
+                buf[row * cols + cols - 1] = buf[row * cols];
+            }
+        }
+    }
+}
+
+impl Default for Boundaries {
+    fn default() -> Boundaries {
+        Boundaries::uniform(BoundaryCondition::Fixed(0.0))
+    }
+}
+
+impl WaveEstimator {
+    // Writes the next value of every edge cell that is not integrated directly, leaving solid
+    // ones at rest. Sides are handled x first, then y, so corner cells follow the condition of
+    // their y side.
+    pub(super) fn apply_boundaries(&mut self, dt: f32) {
+        let (rows, cols) = (self.rows, self.cols);
+        let b = self.boundaries;
+        let steps = [self.domain.dx(), self.domain.dx(), self.domain.dy(), self.domain.dy()];
+
+        for (&(condition, start, along, len, inward), &step) in b.sides(rows, cols).iter().zip(steps.iter()) {
+            for k in 0..len {
+                let i = start + k * along;
+                if self.solid[i] {
+                    continue;
+                }
+
+                match condition {
+                    BoundaryCondition::Fixed(v) => {
+                        self.next_vals[i] = v;
+                        self.speeds[i] = 0.0;
+                    },
+                    BoundaryCondition::Absorbing => {
+                        let inner = (i as isize + inward) as usize;
+                        let c_dt = self.alphas[i].sqrt() * dt;
+                        let mur = (c_dt - step) / (c_dt + step);
+                        let next = self.vals[inner] + mur * (self.next_vals[inner] - self.vals[i]);
+                        self.speeds[i] = (next - self.vals[i]) / dt;
+                        self.next_vals[i] = next;
+                    },
+                    BoundaryCondition::Free | BoundaryCondition::Periodic => {},
+                }
+            }
+        }
+
+        b.sync_periodic(rows, cols, &mut self.next_vals);
+        b.sync_periodic(rows, cols, &mut self.speeds);
+    }
+}
@@ -0,0 +1,315 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::npy::{self, NpzWriter};
+use super::{
+    Boundaries, BoundaryCondition, Domain, Error, Integrator, Interpolation, PointSource, Settings,
+    Stability, WaveEstimator,
+};
+
+const MAGIC: &[u8; 4] = b"WAVE";
+const VERSION: u32 = 1;
+
+// Checkpoints hold everything that affects how the simulation evolves, so a loaded estimator
+// continues bit for bit the same as the one that was saved. Version 1 is laid out as, with every
+// number little-endian:
+//
+//   magic "WAVE", version: u32, nx: i32, ny: i32, width: f32, height: f32, time: f32, damping: f32,
+//   4 boundaries (x_min, x_max, y_min, y_max) as kind: u8 + fixed value: f32,
+//   integrator: u8, stability kind: u8 + max_substeps: u32, interpolation: u8,
+//   next_source_id: u64, source count: u32, sources as id: u64 + x, y, amplitude, frequency, phase: f32,
+//   alphas, values and velocities as (2 * nx + 1) * (2 * ny + 1) f32 each, row-major with one row per x,
+//   followed by the mask as one u8 per cell, 1 for solid
+impl WaveEstimator {
+    pub fn save(&self, writer: impl Write) -> Result<(), Error> {
+        let mut w = BufWriter::new(writer);
+
+        w.write_all(MAGIC)?;
+        write_u32(&mut w, VERSION)?;
+        write_i32(&mut w, self.domain.nx)?;
+        write_i32(&mut w, self.domain.ny)?;
+        write_f32(&mut w, self.domain.width)?;
+        write_f32(&mut w, self.domain.height)?;
+        write_f32(&mut w, self.time)?;
+        write_f32(&mut w, self.damping)?;
+
+        let b = self.boundaries;
+        for &condition in [b.x_min, b.x_max, b.y_min, b.y_max].iter() {
+            let (kind, value) = match condition {
+                BoundaryCondition::Fixed(value) => (0, value),
+                BoundaryCondition::Free => (1, 0.0),
+                BoundaryCondition::Periodic => (2, 0.0),
+                BoundaryCondition::Absorbing => (3, 0.0),
+            };
+            write_u8(&mut w, kind)?;
+            write_f32(&mut w, value)?;
+        }
+
+        write_u8(&mut w, match self.integrator {
+            Integrator::SymplecticEuler => 0,
+            Integrator::Leapfrog => 1,
+            Integrator::Rk4 => 2,
+        })?;
+        match self.stability {
+            Stability::Strict => {
+                write_u8(&mut w, 0)?;
+                write_u32(&mut w, 0)?;
+            },
+            Stability::Substep { max_substeps } => {
+                write_u8(&mut w, 1)?;
+                write_u32(&mut w, max_substeps)?;
+            },
+        }
+        write_u8(&mut w, match self.interpolation {
+            Interpolation::Nearest => 0,
+            Interpolation::Bilinear => 1,
+            Interpolation::Bicubic => 2,
+        })?;
+
+        write_u64(&mut w, self.next_source_id as u64)?;
+        write_u32(&mut w, self.sources.len() as u32)?;
+        for &(id, source) in self.sources.iter() {
+            write_u64(&mut w, id as u64)?;
+            for &x in [source.x, source.y, source.amplitude, source.frequency, source.phase].iter() {
+                write_f32(&mut w, x)?;
+            }
+        }
+
+        for field in [&self.alphas, &self.vals, &self.speeds].iter() {
+            for &x in field.iter() {
+                write_f32(&mut w, x)?;
+            }
+        }
+        for &solid in self.solid.iter() {
+            write_u8(&mut w, solid as u8)?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn load(reader: impl Read) -> Result<WaveEstimator, Error> {
+        let mut r = BufReader::new(reader);
+
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::NotACheckpoint);
+        }
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(Error::UnsupportedCheckpointVersion { version });
+        }
+
+        let domain = Domain::new(read_i32(&mut r)?, read_i32(&mut r)?, read_f32(&mut r)?, read_f32(&mut r)?);
+        domain.validate()?;
+        let time = read_f32(&mut r)?;
+        let damping = read_f32(&mut r)?;
+
+        let mut sides = [BoundaryCondition::Free; 4];
+        for side in sides.iter_mut() {
+            let kind = read_u8(&mut r)?;
+            let value = read_f32(&mut r)?;
+            *side = match kind {
+                0 => BoundaryCondition::Fixed(value),
+                1 => BoundaryCondition::Free,
+                2 => BoundaryCondition::Periodic,
+                3 => BoundaryCondition::Absorbing,
+                _ => return Err(invalid("boundary condition", kind)),
+            };
+        }
+        let boundaries = Boundaries { x_min: sides[0], x_max: sides[1], y_min: sides[2], y_max: sides[3] };
+        boundaries.validate()?;
+
+        let integrator = match read_u8(&mut r)? {
+            0 => Integrator::SymplecticEuler,
+            1 => Integrator::Leapfrog,
+            2 => Integrator::Rk4,
+            kind => return Err(invalid("integrator", kind)),
+        };
+        let stability = match (read_u8(&mut r)?, read_u32(&mut r)?) {
+            (0, _) => Stability::Strict,
+            (1, max_substeps) => Stability::Substep { max_substeps },
+            (kind, _) => return Err(invalid("stability", kind)),
+        };
+        let interpolation = match read_u8(&mut r)? {
+            0 => Interpolation::Nearest,
+            1 => Interpolation::Bilinear,
+            2 => Interpolation::Bicubic,
+            kind => return Err(invalid("interpolation", kind)),
+        };
+
+        let next_source_id = read_u64(&mut r)? as usize;
+        let source_count = read_u32(&mut r)?;
+        let mut sources = Vec::new();
+        for _ in 0..source_count {
+            let id = read_u64(&mut r)? as usize;
+            let source = PointSource {
+                x: read_f32(&mut r)?,
+                y: read_f32(&mut r)?,
+                amplitude: read_f32(&mut r)?,
+                frequency: read_f32(&mut r)?,
+                phase: read_f32(&mut r)?,
+            };
+            sources.push((id, source));
+        }
+
+        let settings = Settings { boundaries, integrator, stability, interpolation, dt: None };
+        let mut estimator = WaveEstimator::build(domain, 0.0, settings, |_, _| 0.0);
+
+        for field in [&mut estimator.alphas, &mut estimator.vals, &mut estimator.speeds].iter_mut() {
+            for x in field.iter_mut() {
+                *x = read_f32(&mut r)?;
+            }
+        }
+        for solid in estimator.solid.iter_mut() {
+            *solid = read_u8(&mut r)? != 0;
+        }
+
+        estimator.update_max_alpha();
+        estimator.time = time;
+        estimator.damping = damping;
+        estimator.sources = sources;
+        estimator.next_source_id = next_source_id;
+
+        Ok(estimator)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), Error> {
+        self.save(File::create(path)?)
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<WaveEstimator, Error> {
+        WaveEstimator::load(File::open(path)?)
+    }
+
+    // Writes the values as a single (rows, cols) .npy array, indexed [x][y]
+    pub fn save_npy(&self, writer: impl Write) -> Result<(), Error> {
+        let mut w = BufWriter::new(writer);
+
+        w.write_all(&npy::npy_bytes(npy::F32, &[self.rows, self.cols], &npy::f32_bytes(&self.vals)))?;
+        w.flush()?;
+        Ok(())
+    }
+
+    // Writes the state as an .npz archive with the `vals`, `speeds`, `alphas` and boolean `solid`
+    // arrays, indexed [x][y], along with the `nx`, `ny`, `width`, `height`, `time` and `damping` scalars
+    pub fn save_npz(&self, writer: impl Write) -> Result<(), Error> {
+        let shape = [self.rows, self.cols];
+        let mut npz = NpzWriter::new(BufWriter::new(writer));
+
+        npz.add("vals", npy::F32, &shape, &npy::f32_bytes(&self.vals))?;
+        npz.add("speeds", npy::F32, &shape, &npy::f32_bytes(&self.speeds))?;
+        npz.add("alphas", npy::F32, &shape, &npy::f32_bytes(&self.alphas))?;
+        npz.add("solid", npy::BOOL, &shape, &self.solid.iter().map(|&s| s as u8).collect::<Vec<_>>())?;
+        npz.add("nx", npy::I32, &[], &self.domain.nx.to_le_bytes())?;
+        npz.add("ny", npy::I32, &[], &self.domain.ny.to_le_bytes())?;
+        npz.add("width", npy::F32, &[], &self.domain.width.to_le_bytes())?;
+        npz.add("height", npy::F32, &[], &self.domain.height.to_le_bytes())?;
+        npz.add("time", npy::F32, &[], &self.time.to_le_bytes())?;
+        npz.add("damping", npy::F32, &[], &self.damping.to_le_bytes())?;
+        npz.finish()?;
+
+        Ok(())
+    }
+}
+
+fn invalid(field: &str, kind: u8) -> Error {
+    Error::InvalidCheckpoint { reason: format!("unknown {} {}", field, kind) }
+}
+
+fn write_u8(w: &mut impl Write, x: u8) -> io::Result<()> {
+    w.write_all(&[x])
+}
+
+fn write_u32(w: &mut impl Write, x: u32) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+fn write_u64(w: &mut impl Write, x: u64) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+fn write_i32(w: &mut impl Write, x: i32) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+fn write_f32(w: &mut impl Write, x: f32) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_i32(r: &mut impl Read) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn read_f32(r: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wave_estimator::{
+        Boundaries, BoundaryCondition, Integrator, PointSource, Settings, Shape, Stability, WaveEstimator,
+    };
+
+    #[test]
+    fn loaded_checkpoint_resumes_exactly_where_it_stopped() {
+        let settings = Settings {
+            boundaries: Boundaries {
+                x_min: BoundaryCondition::Absorbing,
+                x_max: BoundaryCondition::Fixed(0.05),
+                y_min: BoundaryCondition::Periodic,
+                y_max: BoundaryCondition::Periodic,
+            },
+            integrator: Integrator::Leapfrog,
+            stability: Stability::Substep { max_substeps: 16 },
+            ..Settings::default()
+        };
+        let mut saved = WaveEstimator::new_with_settings(41, 5.0, settings, |x, y| 0.1 * (2.0 * x + y).sin())
+            .unwrap();
+        saved.set_alpha_field(|x, _| 3.0 + x);
+        saved.set_damping(0.2);
+        saved.add_obstacle(Shape::Rect { x_min: 0.1, y_min: -0.3, x_max: 0.2, y_max: 0.3 });
+        saved.add_source(PointSource { x: -0.4, y: 0.2, amplitude: 0.2, frequency: 3.0, phase: 0.1 });
+        for _ in 0..20 {
+            saved.update(0.01).unwrap();
+        }
+
+        let mut bytes = Vec::new();
+        saved.save(&mut bytes).unwrap();
+        let mut loaded = WaveEstimator::load(&bytes[..]).unwrap();
+
+        for _ in 0..30 {
+            saved.update(0.01).unwrap();
+            loaded.update(0.01).unwrap();
+        }
+
+        let bits = |values: &[f32]| values.iter().map(|v| v.to_bits()).collect::<Vec<u32>>();
+        assert_eq!(bits(&saved.vals), bits(&loaded.vals));
+        assert_eq!(bits(&saved.speeds), bits(&loaded.speeds));
+        assert_eq!(saved.time.to_bits(), loaded.time.to_bits());
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::io::Write;
+
+use super::{BoundaryCondition, Error, WaveEstimator};
+
+// Snapshot of the energy and shape of the surface at one point in time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Diagnostics {
+    pub time: f32,
+    // ½ ∫ u_t² over the domain
+    pub kinetic: f32,
+    // ½ ∫ alpha |∇u|² over the domain
+    pub potential: f32,
+    pub total: f32,
+    pub max_amplitude: f32,
+    pub mean_displacement: f32,
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "t = {:.2}  E = {:.5} (K = {:.5}, U = {:.5})  max |u| = {:.4}  mean u = {:.4}",
+            self.time, self.total, self.kinetic, self.potential, self.max_amplitude, self.mean_displacement,
+        )
+    }
+}
+
+// Writes one CSV row of diagnostics for every update of the estimator it is attached to
+pub struct DiagnosticsLog {
+    writer: Box<dyn Write>,
+}
+
+impl DiagnosticsLog {
+    pub fn new(mut writer: Box<dyn Write>) -> Result<DiagnosticsLog, Error> {
+        writeln!(writer, "time,kinetic,potential,total,max_amplitude,mean_displacement")?;
+
+        Ok(DiagnosticsLog { writer })
+    }
+
+    pub fn record(&mut self, d: &Diagnostics) -> Result<(), Error> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{}",
+            d.time, d.kinetic, d.potential, d.total, d.max_amplitude, d.mean_displacement,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl WaveEstimator {
+    // Kinetic energy, potential energy and their sum are integrals over the domain, using the
+    // forward difference between every pair of neighbouring cells for the gradient. Without
+    // damping, sources or absorbing edges the total should stay close to constant.
+    pub fn diagnostics(&self) -> Diagnostics {
+        let (rows, cols) = (self.rows, self.cols);
+        let (dx, dy) = (self.domain.dx(), self.domain.dy());
+        let b = self.boundaries;
+        // The last row or column of a periodic axis is a copy of the first one
+        let distinct_rows = if b.x_max == BoundaryCondition::Periodic { rows - 1 } else { rows };
+        let distinct_cols = if b.y_max == BoundaryCondition::Periodic { cols - 1 } else { cols };
+
+        let mut kinetic = 0.0;
+        let mut potential_x = 0.0;
+        let mut potential_y = 0.0;
+        let mut max_amplitude: f32 = 0.0;
+        let mut displacement = 0.0;
+
+        for row in 0..distinct_rows {
+            for col in 0..distinct_cols {
+                let i = row * cols + col;
+                let val = self.vals[i];
+
+                kinetic += 0.5 * self.speeds[i] * self.speeds[i];
+                max_amplitude = max_amplitude.max(val.abs());
+                displacement += val;
+            }
+        }
+
+        // Each difference is squared and divided by its step², then weighted by the dx * dy
+        // cell area. Every pair of neighbours is counted once: the pairs reaching the last row or
+        // column of a periodic axis are the wrap-around ones, but the copied line itself is not
+        // counted again along the other axis.
+        for row in 0..rows - 1 {
+            for col in 0..distinct_cols {
+                let i = row * cols + col;
+                let du = self.vals[i + cols] - self.vals[i];
+                potential_x += 0.25 * (self.alphas[i] + self.alphas[i + cols]) * du * du;
+            }
+        }
+        for row in 0..distinct_rows {
+            for col in 0..cols - 1 {
+                let i = row * cols + col;
+                let du = self.vals[i + 1] - self.vals[i];
+                potential_y += 0.25 * (self.alphas[i] + self.alphas[i + 1]) * du * du;
+            }
+        }
+
+        kinetic *= dx * dy;
+        let potential = potential_x * (dy / dx) + potential_y * (dx / dy);
+
+        Diagnostics {
+            time: self.time,
+            kinetic,
+            potential,
+            total: kinetic + potential,
+            max_amplitude,
+            mean_displacement: displacement / (distinct_rows * distinct_cols) as f32,
+        }
+    }
+
+    // Logs the diagnostics after every call to `update`, or stops logging when given `None`
+    pub fn set_diagnostics_log(&mut self, log: Option<DiagnosticsLog>) {
+        self.diagnostics_log = log;
+    }
+
+    pub fn take_diagnostics_log(&mut self) -> Option<DiagnosticsLog> {
+        self.diagnostics_log.take()
+    }
+
+    pub(super) fn record_diagnostics(&mut self) -> Result<(), Error> {
+        if self.diagnostics_log.is_none() {
+            return Ok(());
+        }
+
+        let diagnostics = self.diagnostics();
+        if let Some(log) = self.diagnostics_log.as_mut() {
+            log.record(&diagnostics)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::wave_estimator::{Boundaries, BoundaryCondition, Integrator, Settings, WaveEstimator};
+
+    #[test]
+    fn energy_of_a_periodic_domain_is_conserved() {
+        let settings = Settings {
+            boundaries: Boundaries::uniform(BoundaryCondition::Periodic),
+            integrator: Integrator::Leapfrog,
+            ..Settings::default()
+        };
+        // A bump off the center, so that waves keep crossing the wrapped edges
+        let mut estimator = WaveEstimator::new_with_settings(20, 1.0, settings, |x, y| {
+            0.1 * (PI * (x - 0.3)).cos().max(0.0).powi(4) * (PI * (y + 0.5)).cos().max(0.0).powi(4)
+        }).unwrap();
+
+        let start = estimator.diagnostics().total;
+        for _ in 0..400 {
+            estimator.update(0.01).unwrap();
+            let total = estimator.diagnostics().total;
+            assert!((total - start).abs() < 0.005 * start, "energy went from {} to {}", start, total);
+        }
+    }
+}
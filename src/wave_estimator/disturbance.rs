@@ -0,0 +1,115 @@
+use super::{BoundaryCondition, WaveEstimator};
+
+// Point that keeps driving the surface with amplitude * sin(2π * frequency * t + phase)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PointSource {
+    pub x: f32,
+    pub y: f32,
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub phase: f32,
+}
+
+impl WaveEstimator {
+    // Kicks the velocity of the lattice point closest to (x, y)
+    pub fn add_impulse(&mut self, x: f32, y: f32, strength: f32) {
+        if let Some(i) = self.closest_cell(x, y) {
+            self.speeds[i] += strength;
+        }
+
+        self.restore_boundaries();
+    }
+
+    // Adds a gaussian bump of the given height and standard deviation centered at (x, y)
+    pub fn add_drop(&mut self, x: f32, y: f32, amplitude: f32, radius: f32) {
+        let domain = self.domain;
+        // Past three standard deviations the bump is negligible
+        let reach_x = (3.0 * radius / domain.dx()).ceil() as i32;
+        let reach_y = (3.0 * radius / domain.dy()).ceil() as i32;
+        let center_x = (x / domain.dx()).round() as i32;
+        let center_y = (y / domain.dy()).round() as i32;
+
+        for lx in (center_x - reach_x).max(-domain.nx)..(center_x + reach_x + 1).min(domain.nx + 1) {
+            for ly in (center_y - reach_y).max(-domain.ny)..(center_y + reach_y + 1).min(domain.ny + 1) {
+                let (px, py) = domain.point(lx, ly);
+                let dx = px - x;
+                let dy = py - y;
+
+                let i = self.idx(lx, ly);
+                self.vals[i] += amplitude * (-(dx * dx + dy * dy) / (2.0 * radius * radius)).exp();
+            }
+        }
+
+        self.restore_boundaries();
+    }
+
+    // Registers a source that drives the surface on every step, returning an id to remove it by
+    pub fn add_source(&mut self, source: PointSource) -> usize {
+        let id = self.next_source_id;
+        self.next_source_id += 1;
+        self.sources.push((id, source));
+
+        id
+    }
+
+    pub fn remove_source(&mut self, id: usize) -> Option<PointSource> {
+        let position = self.sources.iter().position(|&(source_id, _)| source_id == id)?;
+        Some(self.sources.remove(position).1)
+    }
+
+    pub fn clear_sources(&mut self) {
+        self.sources.clear();
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item=&PointSource> {
+        self.sources.iter().map(|(_, source)| source)
+    }
+
+    // Simulation time elapsed since the estimator was created
+    pub fn elapsed(&self) -> f32 {
+        self.time
+    }
+
+    // Forces the cells under every source to its value at the end of a step of `dt`
+    pub(super) fn apply_sources(&mut self, dt: f32) {
+        let t = self.time + dt;
+
+        for k in 0..self.sources.len() {
+            let source = self.sources[k].1;
+            let i = match self.closest_cell(source.x, source.y) {
+                Some(i) if !self.solid[i] => i,
+                _ => continue,
+            };
+
+            let omega = 2.0 * std::f32::consts::PI * source.frequency;
+            self.next_vals[i] = source.amplitude * (omega * t + source.phase).sin();
+            self.speeds[i] = source.amplitude * omega * (omega * t + source.phase).cos();
+        }
+    }
+
+    pub(super) fn closest_cell(&self, x: f32, y: f32) -> Option<usize> {
+        let (x_rounded, y_rounded) = self.domain.closest_point(x, y)?;
+
+        Some(self.idx(x_rounded, y_rounded))
+    }
+
+    // Undoes any disturbance that landed on a fixed edge or a solid cell, and keeps periodic
+    // edges in sync
+    fn restore_boundaries(&mut self) {
+        let (rows, cols) = (self.rows, self.cols);
+        let b = self.boundaries;
+
+        for &(condition, start, along, len, _) in b.sides(rows, cols).iter() {
+            if let BoundaryCondition::Fixed(_) = condition {
+                for k in 0..len {
+                    self.speeds[start + k * along] = 0.0;
+                }
+            }
+        }
+
+        b.apply_fixed(rows, cols, &mut self.vals);
+        b.sync_periodic(rows, cols, &mut self.vals);
+        b.sync_periodic(rows, cols, &mut self.speeds);
+        self.apply_mask();
+    }
+}
@@ -0,0 +1,94 @@
+use super::Error;
+
+// Rectangle of `width` by `height` centered on the origin, sampled by a lattice with `nx` steps
+// from the center to either x edge and `ny` steps to either y edge
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Domain {
+    pub nx: i32,
+    pub ny: i32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Domain {
+    pub fn new(nx: i32, ny: i32, width: f32, height: f32) -> Domain {
+        Domain { nx, ny, width, height }
+    }
+
+    // The [-1, 1]² square with the same number of steps on both axes
+    pub fn square(granularity: i32) -> Domain {
+        Domain::new(granularity, granularity, 2.0, 2.0)
+    }
+
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        if self.nx <= 0 || self.ny <= 0 || !(self.width > 0.0) || !(self.height > 0.0) {
+            return Err(Error::InvalidDomain { domain: *self });
+        }
+        Ok(())
+    }
+
+    // Number of lattice points along x, i.e. 2 * nx + 1
+    pub fn rows(&self) -> usize {
+        (2 * self.nx + 1) as usize
+    }
+
+    // Number of lattice points along y, i.e. 2 * ny + 1
+    pub fn cols(&self) -> usize {
+        (2 * self.ny + 1) as usize
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows() * self.cols()
+    }
+
+    // Distance between neighbouring lattice points along x
+    pub fn dx(&self) -> f32 {
+        self.width / (2 * self.nx) as f32
+    }
+
+    // Distance between neighbouring lattice points along y
+    pub fn dy(&self) -> f32 {
+        self.height / (2 * self.ny) as f32
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x.abs() <= 0.5 * self.width && y.abs() <= 0.5 * self.height
+    }
+
+    // Position of the lattice point `x` steps along x and `y` steps along y from the center
+    pub fn point(&self, x: i32, y: i32) -> (f32, f32) {
+        (
+            x as f32 / self.nx as f32 * (0.5 * self.width),
+            y as f32 / self.ny as f32 * (0.5 * self.height),
+        )
+    }
+
+    // Steps along x and y of the lattice point closest to (x, y), if that lies within the domain
+    pub fn closest_point(&self, x: f32, y: f32) -> Option<(i32, i32)> {
+        let x_rounded = (x / self.dx()).round() as i32;
+        let y_rounded = (y / self.dy()).round() as i32;
+
+        if x_rounded < -self.nx ||
+            x_rounded > self.nx ||
+            y_rounded < -self.ny ||
+            y_rounded > self.ny {
+            return None;
+        }
+
+        Some((x_rounded, y_rounded))
+    }
+
+    // Every lattice point as (x steps, y steps, position), one row of constant x after another
+    pub fn points(&self) -> impl Iterator<Item=(i32, i32, (f32, f32))> {
+        let domain = *self;
+
+        (-domain.nx..(domain.nx + 1)).flat_map(move |x| {
+            (-domain.ny..(domain.ny + 1)).map(move |y| (x, y, domain.point(x, y)))
+        })
+    }
+
+    // Row-major index of the lattice point `x` steps along x and `y` steps along y from the center
+    pub fn idx(&self, x: i32, y: i32) -> usize {
+        (x + self.nx) as usize * self.cols() + (y + self.ny) as usize
+    }
+}
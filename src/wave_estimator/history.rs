@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+
+use super::{PointSource, WaveEstimator};
+
+// State of the surface at one point in time. Parameters such as the alpha field, damping, mask
+// and boundaries are not part of it, so restoring a snapshot keeps their current values.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    time: f32,
+    vals: Vec<f32>,
+    speeds: Vec<f32>,
+    sources: Vec<(usize, PointSource)>,
+    next_source_id: usize,
+}
+
+impl Snapshot {
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+}
+
+// Bounded record of past states, taken every `interval` updates, which the estimator can be
+// scrubbed back and forth through
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+    interval: u32,
+    updates_since_snapshot: u32,
+    // Snapshot the estimator was last scrubbed to, while it has not been updated since
+    cursor: Option<usize>,
+}
+
+impl History {
+    fn new(capacity: usize, interval: u32) -> History {
+        History {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            interval: interval.max(1),
+            updates_since_snapshot: 0,
+            cursor: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn snapshots(&self) -> impl Iterator<Item=&Snapshot> {
+        self.snapshots.iter()
+    }
+
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(snapshot);
+    }
+}
+
+impl WaveEstimator {
+    // Starts recording a snapshot every `interval` updates, keeping the last `capacity` of them.
+    // The current state is recorded straight away.
+    pub fn enable_history(&mut self, capacity: usize, interval: u32) {
+        let mut history = History::new(capacity, interval);
+        history.push(self.snapshot());
+
+        self.history = Some(history);
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            time: self.time,
+            vals: self.vals.clone(),
+            speeds: self.speeds.clone(),
+            sources: self.sources.clone(),
+            next_source_id: self.next_source_id,
+        }
+    }
+
+    // Brings the surface back to the state of the snapshot, which must come from an estimator
+    // over the same domain
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.time = snapshot.time;
+        self.vals.copy_from_slice(&snapshot.vals);
+        self.speeds.copy_from_slice(&snapshot.speeds);
+        self.sources.clone_from(&snapshot.sources);
+        self.next_source_id = snapshot.next_source_id;
+    }
+
+    // Moves `offset` snapshots back (negative) or forward (positive) through the history and
+    // restores that state, returning its time. The state the first scrub starts from is
+    // recorded, so scrubbing forward again can get back to it. The next update resumes from the
+    // restored state, dropping every snapshot after it.
+    pub fn scrub(&mut self, offset: isize) -> Option<f32> {
+        if self.history.as_ref()?.cursor.is_none() {
+            let present = self.snapshot();
+            let history = self.history.as_mut()?;
+
+            history.push(present);
+            history.cursor = Some(history.len() - 1);
+        }
+
+        let history = self.history.as_mut()?;
+        let last = history.len() as isize - 1;
+        let cursor = (history.cursor? as isize + offset).max(0).min(last) as usize;
+        history.cursor = Some(cursor);
+
+        let snapshot = history.snapshots[cursor].clone();
+        self.restore(&snapshot);
+
+        Some(self.time)
+    }
+
+    pub fn is_scrubbing(&self) -> bool {
+        self.history.as_ref().map_or(false, |history| history.cursor.is_some())
+    }
+
+    // Called before every update, so the simulation carries on from a scrubbed-to state
+    pub(super) fn resume_from_history(&mut self) {
+        if let Some(history) = self.history.as_mut() {
+            if let Some(cursor) = history.cursor.take() {
+                history.snapshots.truncate(cursor + 1);
+                history.updates_since_snapshot = 0;
+            }
+        }
+    }
+
+    // Called after every update
+    pub(super) fn record_history(&mut self) {
+        let due = match self.history.as_mut() {
+            Some(history) => {
+                history.updates_since_snapshot += 1;
+                history.updates_since_snapshot >= history.interval
+            },
+            None => false,
+        };
+
+        if due {
+            let snapshot = self.snapshot();
+            if let Some(history) = self.history.as_mut() {
+                history.push(snapshot);
+                history.updates_since_snapshot = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::WaveEstimator;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Integrator {
+    // Semi-implicit Euler: velocity first, then position with the new velocity
+    SymplecticEuler,
+    // Velocity Verlet, with a half velocity kick on either side of the position drift
+    Leapfrog,
+    // Classic fourth order Runge-Kutta on the (value, velocity) system
+    Rk4,
+}
+
+impl Integrator {
+    // Largest Courant number sqrt(alpha) * dt / step for which the scheme stays stable with the
+    // five point laplacian
+    pub fn courant_limit(&self) -> f32 {
+        match self {
+            Integrator::SymplecticEuler | Integrator::Leapfrog => std::f32::consts::FRAC_1_SQRT_2,
+            Integrator::Rk4 => 1.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stability {
+    // Time steps above the CFL limit are rejected with an error
+    Strict,
+    // Time steps above the CFL limit are split into equal stable sub-steps, failing if that
+    // needs more than `max_substeps` of them
+    Substep { max_substeps: u32 },
+}
+
+// Buffers for the intermediate stages of RK4, allocated once when the integrator is selected
+pub(super) struct Rk4Scratch {
+    stage_vals: Vec<f32>,
+    stage_speeds: Vec<f32>,
+    k_vals: Vec<f32>,
+    k_speeds: Vec<f32>,
+    sum_vals: Vec<f32>,
+    sum_speeds: Vec<f32>,
+}
+
+impl Rk4Scratch {
+    pub fn new(len: usize) -> Rk4Scratch {
+        Rk4Scratch {
+            stage_vals: vec![0.0; len],
+            stage_speeds: vec![0.0; len],
+            k_vals: vec![0.0; len],
+            k_speeds: vec![0.0; len],
+            sum_vals: vec![0.0; len],
+            sum_speeds: vec![0.0; len],
+        }
+    }
+}
+
+impl WaveEstimator {
+    // Advances the state by exactly `dt`, without any stability check
+    pub(super) fn step(&mut self, dt: f32) {
+        match self.integrator {
+            Integrator::SymplecticEuler => {
+                self.step_symplectic_euler(dt);
+                self.apply_boundaries(dt);
+            },
+            Integrator::Leapfrog => {
+                self.leapfrog_drift(dt);
+                self.apply_boundaries(dt);
+                self.leapfrog_kick(dt);
+            },
+            Integrator::Rk4 => {
+                self.step_rk4(dt);
+                self.apply_boundaries(dt);
+            },
+        }
+
+        self.apply_sources(dt);
+        self.time += dt;
+
+        std::mem::swap(&mut self.vals, &mut self.next_vals);
+    }
+
+    // Damping is treated implicitly in every velocity update, so that it can never make the
+    // velocity overshoot. Solid cells feel no acceleration, so they stay at rest.
+    fn step_symplectic_euler(&mut self, dt: f32) {
+        let stencil = self.stencil();
+        let damping = self.damping;
+        let alphas = &self.alphas;
+        let solid = &self.solid;
+        let vals = &self.vals;
+        let speeds = &mut self.speeds;
+        let next_vals = &mut self.next_vals;
+
+        let kernel = |i: usize, laplacian: f32, speed: &mut f32, next_val: &mut f32| {
+            let alpha = if solid[i] { 0.0 } else { alphas[i] };
+            *speed = (*speed + alpha * laplacian * dt) / (1.0 + damping * dt);
+            *next_val = vals[i] + *speed * dt;
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            if self.parallel {
+                let band_len = stencil.band_len();
+                let bands = speeds.par_chunks_mut(band_len).zip(next_vals.par_chunks_mut(band_len));
+
+                stencil.par_for_each_in_bands(vals, bands, |i, j, laplacian, (speeds, next_vals)| {
+                    kernel(i, laplacian, &mut speeds[j], &mut next_vals[j]);
+                });
+                stencil.for_each_edge(vals, |i, laplacian| {
+                    kernel(i, laplacian, &mut speeds[i], &mut next_vals[i]);
+                });
+                return;
+            }
+        }
+
+        stencil.for_each(vals, |i, laplacian| {
+            kernel(i, laplacian, &mut speeds[i], &mut next_vals[i]);
+        });
+    }
+
+    // First half kick followed by the full drift
+    fn leapfrog_drift(&mut self, dt: f32) {
+        let stencil = self.stencil();
+        let half_dt = 0.5 * dt;
+        let damping = self.damping;
+        let alphas = &self.alphas;
+        let solid = &self.solid;
+        let vals = &self.vals;
+        let speeds = &mut self.speeds;
+        let next_vals = &mut self.next_vals;
+
+        let kernel = |i: usize, laplacian: f32, speed: &mut f32, next_val: &mut f32| {
+            let alpha = if solid[i] { 0.0 } else { alphas[i] };
+            *speed = (*speed + alpha * laplacian * half_dt) / (1.0 + damping * half_dt);
+            *next_val = vals[i] + *speed * dt;
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            if self.parallel {
+                let band_len = stencil.band_len();
+                let bands = speeds.par_chunks_mut(band_len).zip(next_vals.par_chunks_mut(band_len));
+
+                stencil.par_for_each_in_bands(vals, bands, |i, j, laplacian, (speeds, next_vals)| {
+                    kernel(i, laplacian, &mut speeds[j], &mut next_vals[j]);
+                });
+                stencil.for_each_edge(vals, |i, laplacian| {
+                    kernel(i, laplacian, &mut speeds[i], &mut next_vals[i]);
+                });
+                return;
+            }
+        }
+
+        stencil.for_each(vals, |i, laplacian| {
+            kernel(i, laplacian, &mut speeds[i], &mut next_vals[i]);
+        });
+    }
+
+    // Second half kick, using the accelerations at the drifted positions
+    fn leapfrog_kick(&mut self, dt: f32) {
+        let stencil = self.stencil();
+        let half_dt = 0.5 * dt;
+        let damping = self.damping;
+        let alphas = &self.alphas;
+        let solid = &self.solid;
+        let next_vals = &self.next_vals;
+        let speeds = &mut self.speeds;
+
+        let kernel = |i: usize, laplacian: f32, speed: &mut f32| {
+            let alpha = if solid[i] { 0.0 } else { alphas[i] };
+            *speed = (*speed + alpha * laplacian * half_dt) / (1.0 + damping * half_dt);
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            if self.parallel {
+                let bands = speeds.par_chunks_mut(stencil.band_len());
+
+                stencil.par_for_each_in_bands(next_vals, bands, |i, j, laplacian, speeds| {
+                    kernel(i, laplacian, &mut speeds[j]);
+                });
+                stencil.for_each_edge(next_vals, |i, laplacian| {
+                    kernel(i, laplacian, &mut speeds[i]);
+                });
+                return;
+            }
+        }
+
+        stencil.for_each(next_vals, |i, laplacian| {
+            kernel(i, laplacian, &mut speeds[i]);
+        });
+    }
+
+    fn step_rk4(&mut self, dt: f32) {
+        // (fraction of dt the stage is evaluated at, weight of its slope)
+        const STAGES: [(f32, f32); 4] = [(0.0, 1.0), (0.5, 2.0), (0.5, 2.0), (1.0, 1.0)];
+
+        let stencil = self.stencil();
+        let damping = self.damping;
+        #[cfg(feature = "parallel")]
+        let parallel = self.parallel;
+        let alphas = &self.alphas;
+        let solid = &self.solid;
+        let vals = &self.vals;
+        let speeds = &mut self.speeds;
+        let next_vals = &mut self.next_vals;
+        let Rk4Scratch {
+            stage_vals, stage_speeds, k_vals, k_speeds, sum_vals, sum_speeds
+        } = self.rk4_scratch.as_mut().expect("RK4 buffers are allocated with the integrator");
+
+        for x in sum_vals.iter_mut().chain(sum_speeds.iter_mut()) {
+            *x = 0.0;
+        }
+
+        // Slopes of cells that are not integrated stay at zero, so those cells keep their value
+        // through every stage and are left to `apply_boundaries`
+        for &(fraction, weight) in STAGES.iter() {
+            for i in 0..vals.len() {
+                stage_vals[i] = vals[i] + fraction * dt * k_vals[i];
+                stage_speeds[i] = speeds[i] + fraction * dt * k_speeds[i];
+            }
+
+            let stage_speeds = &*stage_speeds;
+            let kernel = |i: usize, laplacian: f32, k_val: &mut f32, k_speed: &mut f32, sum_val: &mut f32, sum_speed: &mut f32| {
+                let speed = stage_speeds[i];
+                let alpha = if solid[i] { 0.0 } else { alphas[i] };
+                *k_val = speed;
+                *k_speed = alpha * laplacian - damping * speed;
+                *sum_val += weight * *k_val;
+                *sum_speed += weight * *k_speed;
+            };
+
+            #[cfg(feature = "parallel")]
+            {
+                if parallel {
+                    let band_len = stencil.band_len();
+                    let bands = k_vals.par_chunks_mut(band_len)
+                        .zip(k_speeds.par_chunks_mut(band_len))
+                        .zip(sum_vals.par_chunks_mut(band_len))
+                        .zip(sum_speeds.par_chunks_mut(band_len));
+
+                    stencil.par_for_each_in_bands(
+                        stage_vals,
+                        bands,
+                        |i, j, laplacian, (((k_vals, k_speeds), sum_vals), sum_speeds)| {
+                            kernel(i, laplacian, &mut k_vals[j], &mut k_speeds[j], &mut sum_vals[j], &mut sum_speeds[j]);
+                        },
+                    );
+                    stencil.for_each_edge(stage_vals, |i, laplacian| {
+                        kernel(i, laplacian, &mut k_vals[i], &mut k_speeds[i], &mut sum_vals[i], &mut sum_speeds[i]);
+                    });
+                    continue;
+                }
+            }
+
+            stencil.for_each(stage_vals, |i, laplacian| {
+                kernel(i, laplacian, &mut k_vals[i], &mut k_speeds[i], &mut sum_vals[i], &mut sum_speeds[i]);
+            });
+        }
+
+        for i in 0..vals.len() {
+            next_vals[i] = vals[i] + dt / 6.0 * sum_vals[i];
+            speeds[i] += dt / 6.0 * sum_speeds[i];
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use crate::wave_estimator::{Boundaries, BoundaryCondition, PointSource, Settings, Shape, WaveEstimator};
+    use super::{Integrator, Stability};
+
+    // Absorbing edges, uneven alphas, an obstacle, a source and a drop, so that every kind of
+    // cell gets visited by the stepped lattice
+    fn busy_estimator(integrator: Integrator, parallel: bool) -> WaveEstimator {
+        let settings = Settings {
+            boundaries: Boundaries::uniform(BoundaryCondition::Absorbing),
+            integrator,
+            stability: Stability::Substep { max_substeps: 16 },
+            ..Settings::default()
+        };
+        let mut estimator = WaveEstimator::new_with_settings(97, 5.0, settings, |x, y| {
+            0.1 * (3.0 * x).sin() * (2.0 * y).cos()
+        }).unwrap();
+
+        estimator.set_alpha_field(|x, y| 2.0 + 3.0 * (x * x + 0.5 * y).abs().min(1.0));
+        estimator.add_obstacle(Shape::Circle { x: 0.3, y: -0.2, radius: 0.15 });
+        estimator.add_source(PointSource { x: -0.5, y: 0.4, amplitude: 0.2, frequency: 4.0, phase: 0.3 });
+        estimator.add_drop(0.6, 0.6, 0.2, 0.05);
+        estimator.set_parallel(parallel);
+
+        estimator
+    }
+
+    #[test]
+    fn parallel_update_matches_serial_bit_for_bit() {
+        for &integrator in [Integrator::SymplecticEuler, Integrator::Leapfrog, Integrator::Rk4].iter() {
+            let mut serial = busy_estimator(integrator, false);
+            let mut parallel = busy_estimator(integrator, true);
+
+            for _ in 0..50 {
+                serial.update(0.01).unwrap();
+                parallel.update(0.01).unwrap();
+            }
+
+            let bits = |values: &[f32]| values.iter().map(|v| v.to_bits()).collect::<Vec<u32>>();
+            assert_eq!(bits(&serial.vals), bits(&parallel.vals), "values differ with {:?}", integrator);
+            assert_eq!(bits(&serial.speeds), bits(&parallel.speeds), "speeds differ with {:?}", integrator);
+        }
+    }
+}
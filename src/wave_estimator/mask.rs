@@ -0,0 +1,131 @@
+use crate::render_gl::image::GrayImage;
+
+use super::WaveEstimator;
+
+// Region of the domain that can be turned into a solid obstacle
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    Circle { x: f32, y: f32, radius: f32 },
+    Rect { x_min: f32, y_min: f32, x_max: f32, y_max: f32 },
+    // Segment from (x0, y0) to (x1, y1), covering every point closer than thickness / 2 to it
+    Line { x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32 },
+}
+
+impl Shape {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        match *self {
+            Shape::Circle { x: cx, y: cy, radius } => {
+                (x - cx) * (x - cx) + (y - cy) * (y - cy) <= radius * radius
+            },
+            Shape::Rect { x_min, y_min, x_max, y_max } => {
+                x >= x_min && x <= x_max && y >= y_min && y <= y_max
+            },
+            Shape::Line { x0, y0, x1, y1, thickness } => {
+                let (dx, dy) = (x1 - x0, y1 - y0);
+                let len2 = dx * dx + dy * dy;
+                // Position of the closest point along the segment, from 0 at (x0, y0) to 1
+                let t = if len2 > 0.0 {
+                    (((x - x0) * dx + (y - y0) * dy) / len2).max(0.0).min(1.0)
+                } else {
+                    0.0
+                };
+                let (px, py) = (x0 + t * dx - x, y0 + t * dy - y);
+
+                px * px + py * py <= 0.25 * thickness * thickness
+            },
+        }
+    }
+}
+
+// Solid cells stay at rest with a value of 0 whatever happens around them, which makes them
+// reflecting walls for the waves that reach them
+impl WaveEstimator {
+    // Replaces the mask with the cells where `solid_func` is true, over the same coordinates as
+    // `start_func`
+    pub fn set_mask(&mut self, solid_func: impl Fn(f32, f32) -> bool) {
+        let domain = self.domain;
+
+        for (x, y, (x_f, y_f)) in domain.points() {
+            self.solid[domain.idx(x, y)] = solid_func(x_f, y_f);
+        }
+
+        self.apply_mask();
+    }
+
+    // Replaces the mask with the cells under pixels darker than `threshold`, stretching the
+    // image over the domain the same way as heightmaps: x runs along it from left to right and
+    // y from top to bottom
+    pub fn set_mask_from_image(&mut self, image: &GrayImage, threshold: f32) {
+        let domain = self.domain;
+
+        self.set_mask(|x, y| {
+            image.sample_nearest(x / domain.width + 0.5, y / domain.height + 0.5) < threshold
+        });
+    }
+
+    // Makes every cell inside the shape solid, keeping the rest of the mask. Lines are widened
+    // to at least a cell so that waves can not leak through them.
+    pub fn add_obstacle(&mut self, shape: Shape) {
+        let domain = self.domain;
+        let shape = match shape {
+            Shape::Line { x0, y0, x1, y1, thickness } => Shape::Line {
+                x0, y0, x1, y1, thickness: thickness.max(domain.dx().max(domain.dy())),
+            },
+            shape => shape,
+        };
+
+        for (x, y, (x_f, y_f)) in domain.points() {
+            if shape.contains(x_f, y_f) {
+                self.solid[domain.idx(x, y)] = true;
+            }
+        }
+
+        self.apply_mask();
+    }
+
+    pub fn clear_mask(&mut self) {
+        for solid in self.solid.iter_mut() {
+            *solid = false;
+        }
+    }
+
+    // Whether the lattice point closest to (x, y) is solid
+    pub fn is_solid(&self, x: f32, y: f32) -> bool {
+        match self.closest_cell(x, y) {
+            Some(i) => self.solid[i],
+            None => false,
+        }
+    }
+
+    // Brings every solid cell to rest at 0
+    pub(super) fn apply_mask(&mut self) {
+        for (i, &solid) in self.solid.iter().enumerate() {
+            if solid {
+                self.vals[i] = 0.0;
+                self.speeds[i] = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render_gl::image::GrayImage;
+    use crate::wave_estimator::WaveEstimator;
+
+    #[test]
+    fn dark_pixels_of_an_image_become_solid() {
+        // Dark top left and bottom right quarters, with y running down the image
+        let image = GrayImage::new(2, 2, vec![0.0, 1.0, 0.8, 0.2]);
+        let mut estimator = WaveEstimator::new(4, 1.0, |_, _| 0.1);
+        estimator.set_mask_from_image(&image, 0.5);
+
+        assert!(estimator.is_solid(-0.5, -0.5));
+        assert!(!estimator.is_solid(0.5, -0.5));
+        assert!(!estimator.is_solid(-0.5, 0.5));
+        assert!(estimator.is_solid(0.5, 0.5));
+        for &(x, y) in &[(-0.5, -0.5), (0.5, 0.5)] {
+            assert_eq!(estimator.get_val(x, y), 0.0);
+        }
+    }
+}
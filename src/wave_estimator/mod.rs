@@ -0,0 +1,354 @@
+mod boundary;
+mod checkpoint;
+mod diagnostics;
+mod disturbance;
+mod domain;
+mod history;
+mod integrator;
+mod mask;
+mod npy;
+mod sampling;
+mod stencil;
+
+pub use self::boundary::{Boundaries, BoundaryCondition};
+pub use self::diagnostics::{Diagnostics, DiagnosticsLog};
+pub use self::disturbance::PointSource;
+pub use self::domain::Domain;
+pub use self::history::{History, Snapshot};
+pub use self::integrator::{Integrator, Stability};
+pub use self::mask::Shape;
+pub use self::sampling::Interpolation;
+
+use std::io;
+
+use self::integrator::Rk4Scratch;
+use self::stencil::Stencil;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Periodic boundary on the {} axis must be set on both of its sides", axis)]
+    UnpairedPeriodicBoundary { axis: String },
+    #[fail(display = "Expected {} alpha samples but got {}", expected, actual)]
+    AlphaSamplesSizeMismatch { expected: usize, actual: usize },
+    #[fail(display = "Time step {} is above the CFL stability limit of {}", dt, max_dt)]
+    UnstableTimestep { dt: f32, max_dt: f32 },
+    #[fail(display = "Time step {} needs {} stable sub-steps, more than the maximum of {}", dt, required, max_substeps)]
+    TooManySubsteps { dt: f32, required: u32, max_substeps: u32 },
+    #[fail(display = "Invalid domain {:?}, steps and extents must be positive on both axes", domain)]
+    InvalidDomain { domain: Domain },
+    #[fail(display = "I/O error")]
+    Io(#[cause] io::Error),
+    #[fail(display = "File is not a wave estimator checkpoint")]
+    NotACheckpoint,
+    #[fail(display = "Unsupported checkpoint version {}", version)]
+    UnsupportedCheckpointVersion { version: u32 },
+    #[fail(display = "Invalid checkpoint: {}", reason)]
+    InvalidCheckpoint { reason: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Self {
+        Error::Io(other)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub boundaries: Boundaries,
+    pub integrator: Integrator,
+    pub stability: Stability,
+    pub interpolation: Interpolation,
+    // Time step the simulation is meant to run at. When set, it is checked against the CFL
+    // limit as the estimator gets built.
+    pub dt: Option<f32>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            boundaries: Boundaries::default(),
+            integrator: Integrator::SymplecticEuler,
+            stability: Stability::Strict,
+            interpolation: Interpolation::Bilinear,
+            dt: None,
+        }
+    }
+}
+
+pub struct WaveEstimator {
+    domain: Domain,
+    // Per cell alpha, the square of the local wave speed
+    alphas: Vec<f32>,
+    max_alpha: f32,
+    // Cells that act as reflecting walls inside the domain
+    solid: Vec<bool>,
+    damping: f32,
+    boundaries: Boundaries,
+    integrator: Integrator,
+    stability: Stability,
+    interpolation: Interpolation,
+    // Number of lattice points along x and y, cached from the domain
+    rows: usize,
+    cols: usize,
+    // Row-major lattice buffers with one row per x coordinate, indexed through `Domain::idx`.
+    // `next_vals` is scratch space that gets swapped with `vals` after every step so `update`
+    // never allocates.
+    vals: Vec<f32>,
+    next_vals: Vec<f32>,
+    speeds: Vec<f32>,
+    rk4_scratch: Option<Rk4Scratch>,
+    time: f32,
+    sources: Vec<(usize, PointSource)>,
+    next_source_id: usize,
+    diagnostics_log: Option<DiagnosticsLog>,
+    history: Option<History>,
+    // Whether the interior is updated in row bands across the rayon thread pool
+    #[cfg(feature = "parallel")]
+    parallel: bool,
+}
+
+impl WaveEstimator {
+    pub fn new(granularity: i32, alpha: f32, start_func: impl Fn(f32, f32) -> f32) -> WaveEstimator {
+        WaveEstimator::build(Domain::square(granularity), alpha, Settings::default(), start_func)
+    }
+
+    pub fn new_with_boundaries(
+        granularity: i32,
+        alpha: f32,
+        boundaries: Boundaries,
+        start_func: impl Fn(f32, f32) -> f32,
+    ) -> Result<WaveEstimator, Error> {
+        WaveEstimator::new_with_settings(
+            granularity, alpha, Settings { boundaries, ..Settings::default() }, start_func,
+        )
+    }
+
+    pub fn new_with_settings(
+        granularity: i32,
+        alpha: f32,
+        settings: Settings,
+        start_func: impl Fn(f32, f32) -> f32,
+    ) -> Result<WaveEstimator, Error> {
+        WaveEstimator::new_with_domain(Domain::square(granularity), alpha, settings, start_func)
+    }
+
+    // Estimator over a rectangular domain, where `start_func` and every other function of
+    // position take coordinates within [-width / 2, width / 2] x [-height / 2, height / 2]
+    pub fn new_with_domain(
+        domain: Domain,
+        alpha: f32,
+        settings: Settings,
+        start_func: impl Fn(f32, f32) -> f32,
+    ) -> Result<WaveEstimator, Error> {
+        domain.validate()?;
+        settings.boundaries.validate()?;
+
+        let estimator = WaveEstimator::build(domain, alpha, settings, start_func);
+        if let Some(dt) = settings.dt {
+            estimator.substeps_for(dt)?;
+        }
+
+        Ok(estimator)
+    }
+
+    fn build(
+        domain: Domain,
+        alpha: f32,
+        settings: Settings,
+        start_func: impl Fn(f32, f32) -> f32,
+    ) -> WaveEstimator {
+        let (rows, cols) = (domain.rows(), domain.cols());
+        let mut vals = vec![0.0; domain.len()];
+
+        for (x, y, (x_f, y_f)) in domain.points() {
+            vals[domain.idx(x, y)] = start_func(x_f, y_f);
+        }
+
+        settings.boundaries.apply_fixed(rows, cols, &mut vals);
+        settings.boundaries.sync_periodic(rows, cols, &mut vals);
+
+        let mut estimator = WaveEstimator {
+            domain,
+            alphas: vec![alpha; domain.len()],
+            max_alpha: alpha,
+            solid: vec![false; domain.len()],
+            damping: 0.0,
+            boundaries: settings.boundaries,
+            integrator: Integrator::SymplecticEuler,
+            stability: settings.stability,
+            interpolation: settings.interpolation,
+            rows,
+            cols,
+            vals,
+            next_vals: vec![0.0; domain.len()],
+            speeds: vec![0.0; domain.len()],
+            rk4_scratch: None,
+            time: 0.0,
+            sources: Vec::new(),
+            next_source_id: 0,
+            diagnostics_log: None,
+            history: None,
+            #[cfg(feature = "parallel")]
+            parallel: false,
+        };
+        estimator.set_integrator(settings.integrator);
+
+        estimator
+    }
+
+    // Advances the simulation by `dt`. Steps above the CFL limit either fail or get split into
+    // sub-steps, depending on the stability setting.
+    pub fn update(&mut self, dt: f32) -> Result<(), Error> {
+        let substeps = self.substeps_for(dt)?;
+        let sub_dt = dt / substeps as f32;
+        self.resume_from_history();
+
+        for _ in 0..substeps {
+            self.step(sub_dt);
+        }
+
+        self.record_history();
+        self.record_diagnostics()
+    }
+
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
+
+    // Number of lattice points along x and y
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn boundaries(&self) -> Boundaries {
+        self.boundaries
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        if integrator == Integrator::Rk4 && self.rk4_scratch.is_none() {
+            self.rk4_scratch = Some(Rk4Scratch::new(self.vals.len()));
+        }
+
+        self.integrator = integrator;
+    }
+
+    pub fn stability(&self) -> Stability {
+        self.stability
+    }
+
+    pub fn set_stability(&mut self, stability: Stability) {
+        self.stability = stability;
+    }
+
+    // Splits updates across threads. The result is bit for bit the same as the serial update,
+    // since every cell is still computed with the same operations in the same order.
+    #[cfg(feature = "parallel")]
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel = parallel;
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    // Largest time step the current integrator can take without going unstable, given the
+    // fastest wave speed in the domain
+    pub fn max_stable_dt(&self) -> f32 {
+        if self.max_alpha <= 0.0 {
+            return std::f32::INFINITY;
+        }
+
+        // The limit is stated for square cells, where sqrt(1 / dx² + 1 / dy²) is sqrt(2) / step
+        let (dx, dy) = (self.domain.dx(), self.domain.dy());
+        let inv_step = (1.0 / (dx * dx) + 1.0 / (dy * dy)).sqrt() * std::f32::consts::FRAC_1_SQRT_2;
+        self.integrator.courant_limit() / (inv_step * self.max_alpha.sqrt())
+    }
+
+    // Fraction of the velocity lost per unit of time. Zero keeps the scheme energy conserving.
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping;
+    }
+
+    pub fn damping(&self) -> f32 {
+        self.damping
+    }
+
+    // Samples the alpha (squared wave speed) of every cell from a closure over the same
+    // coordinates as `start_func`
+    pub fn set_alpha_field(&mut self, alpha_func: impl Fn(f32, f32) -> f32) {
+        let domain = self.domain;
+
+        for (x, y, (x_f, y_f)) in domain.points() {
+            self.alphas[domain.idx(x, y)] = alpha_func(x_f, y_f);
+        }
+
+        self.update_max_alpha();
+    }
+
+    // Sets the alpha of every cell from a row-major array with one row per x coordinate,
+    // holding `Domain::len` samples
+    pub fn set_alpha_samples(&mut self, samples: &[f32]) -> Result<(), Error> {
+        if samples.len() != self.alphas.len() {
+            return Err(Error::AlphaSamplesSizeMismatch {
+                expected: self.alphas.len(),
+                actual: samples.len(),
+            });
+        }
+
+        self.alphas.copy_from_slice(samples);
+        self.update_max_alpha();
+        Ok(())
+    }
+
+    fn update_max_alpha(&mut self) {
+        self.max_alpha = self.alphas.iter().cloned().fold(0.0, f32::max);
+    }
+
+    // Number of equal sub-steps `dt` has to be split into to stay below the CFL limit
+    fn substeps_for(&self, dt: f32) -> Result<u32, Error> {
+        let max_dt = self.max_stable_dt();
+        if dt <= max_dt {
+            return Ok(1);
+        }
+
+        match self.stability {
+            Stability::Strict => Err(Error::UnstableTimestep { dt, max_dt }),
+            Stability::Substep { max_substeps } => {
+                let required = (dt / max_dt).ceil() as u32;
+                if required > max_substeps {
+                    return Err(Error::TooManySubsteps { dt, required, max_substeps });
+                }
+                Ok(required)
+            },
+        }
+    }
+
+    fn stencil(&self) -> Stencil {
+        let (dx, dy) = (self.domain.dx(), self.domain.dy());
+
+        Stencil {
+            rows: self.rows,
+            cols: self.cols,
+            boundaries: self.boundaries,
+            inv_dx2: 1.0 / (dx * dx),
+            inv_dy2: 1.0 / (dy * dy),
+        }
+    }
+
+    fn idx(&self, x: i32, y: i32) -> usize {
+        self.domain.idx(x, y)
+    }
+}
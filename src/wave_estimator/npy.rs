@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+// Writers for NumPy's .npy arrays and .npz archives, just enough to hand the lattice over to
+// numpy.load. Archives are written uncompressed.
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+// Zip 2.0, the version readers expect for stored entries
+const ZIP_VERSION: u16 = 20;
+
+// Little-endian 32 bit float and integer dtypes, and one byte booleans
+pub const F32: &str = "<f4";
+pub const I32: &str = "<i4";
+pub const BOOL: &str = "|b1";
+
+// Encodes `data`, the raw little-endian elements of a C-ordered array, as a version 1.0 .npy file
+pub fn npy_bytes(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let shape = match shape.len() {
+        0 => "()".to_string(),
+        1 => format!("({},)", shape[0]),
+        _ => format!("({})", shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+
+    // The header is padded with spaces so the data starts on a 64 byte boundary
+    let prefix_len = NPY_MAGIC.len() + 2 + 2;
+    let total = prefix_len + header.len() + 1;
+    header.extend(std::iter::repeat(' ').take((64 - total % 64) % 64));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(prefix_len + header.len() + data.len());
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+
+    bytes
+}
+
+pub fn f32_bytes(data: &[f32]) -> Vec<u8> {
+    data.iter().flat_map(|x| x.to_le_bytes().to_vec()).collect()
+}
+
+// Zip archive of .npy files, stored without compression
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: u32,
+    central_directory: Vec<u8>,
+    entries: u16,
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> NpzWriter<W> {
+        NpzWriter { writer, offset: 0, central_directory: Vec::new(), entries: 0 }
+    }
+
+    // Adds the array as `<name>.npy`, which numpy.load exposes under `name`
+    pub fn add(&mut self, name: &str, descr: &str, shape: &[usize], data: &[u8]) -> io::Result<()> {
+        let file_name = format!("{}.npy", name);
+        let contents = npy_bytes(descr, shape, data);
+        let crc = crc32(&contents);
+        let len = contents.len() as u32;
+
+        let mut local_header = Vec::new();
+        local_header.extend_from_slice(&0x04034b50_u32.to_le_bytes());
+        NpzWriter::<W>::push_entry_fields(&mut local_header, crc, len, file_name.len() as u16);
+        local_header.extend_from_slice(&0_u16.to_le_bytes());
+        local_header.extend_from_slice(file_name.as_bytes());
+
+        self.central_directory.extend_from_slice(&0x02014b50_u32.to_le_bytes());
+        self.central_directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        NpzWriter::<W>::push_entry_fields(&mut self.central_directory, crc, len, file_name.len() as u16);
+        // Extra field and comment lengths, disk number, internal and external attributes
+        for _ in 0..4 {
+            self.central_directory.extend_from_slice(&0_u16.to_le_bytes());
+        }
+        self.central_directory.extend_from_slice(&0_u32.to_le_bytes());
+        self.central_directory.extend_from_slice(&self.offset.to_le_bytes());
+        self.central_directory.extend_from_slice(file_name.as_bytes());
+
+        self.writer.write_all(&local_header)?;
+        self.writer.write_all(&contents)?;
+        self.offset += local_header.len() as u32 + len;
+        self.entries += 1;
+
+        Ok(())
+    }
+
+    // Writes the central directory that closes the archive
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut end = Vec::new();
+        end.extend_from_slice(&0x06054b50_u32.to_le_bytes());
+        end.extend_from_slice(&0_u16.to_le_bytes());
+        end.extend_from_slice(&0_u16.to_le_bytes());
+        end.extend_from_slice(&self.entries.to_le_bytes());
+        end.extend_from_slice(&self.entries.to_le_bytes());
+        end.extend_from_slice(&(self.central_directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&self.offset.to_le_bytes());
+        end.extend_from_slice(&0_u16.to_le_bytes());
+
+        self.writer.write_all(&self.central_directory)?;
+        self.writer.write_all(&end)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    // Fields shared by the local and central headers, from "version needed" to the file name length
+    fn push_entry_fields(bytes: &mut Vec<u8>, crc: u32, len: u32, name_len: u16) {
+        bytes.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        // Flags and compression method (stored)
+        bytes.extend_from_slice(&0_u16.to_le_bytes());
+        bytes.extend_from_slice(&0_u16.to_le_bytes());
+        // Modification time and date, 1980-01-01 00:00
+        bytes.extend_from_slice(&0_u16.to_le_bytes());
+        bytes.extend_from_slice(&0x21_u16.to_le_bytes());
+        bytes.extend_from_slice(&crc.to_le_bytes());
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&name_len.to_le_bytes());
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
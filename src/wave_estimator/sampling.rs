@@ -0,0 +1,115 @@
+use nalgebra_glm as glm;
+
+use super::WaveEstimator;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    // Value of the closest lattice point
+    Nearest,
+    Bilinear,
+    // Catmull-Rom spline through the surrounding 4x4 lattice points
+    Bicubic,
+}
+
+impl WaveEstimator {
+    // Value of the surface at (x, y), interpolated between lattice points. Points outside of the
+    // domain read as 0.
+    pub fn get_val(&self, x: f32, y: f32) -> f32 {
+        let cols = self.cols;
+        self.sample(x, y, |row, col| self.vals[row * cols + col])
+    }
+
+    // Slope (d/dx, d/dy) of the surface at (x, y), interpolated from central differences at the
+    // lattice points
+    pub fn get_gradient(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.sample(x, y, |row, col| self.node_gradient(row, col).0),
+            self.sample(x, y, |row, col| self.node_gradient(row, col).1),
+        )
+    }
+
+    // Unit normal of the surface at (x, y), in the frame the grid renders it in: x and y of the
+    // domain map to the x and z axes and the value is the height along y
+    pub fn get_normal(&self, x: f32, y: f32) -> glm::Vec3 {
+        let (dfdx, dfdy) = self.get_gradient(x, y);
+        glm::normalize(&glm::vec3(-dfdx, 1.0, -dfdy))
+    }
+
+    fn node_gradient(&self, row: usize, col: usize) -> (f32, f32) {
+        let cols = self.cols;
+
+        // One-sided differences on the edges of the lattice
+        let (row_prev, row_next) = (row.saturating_sub(1), (row + 1).min(self.rows - 1));
+        let (col_prev, col_next) = (col.saturating_sub(1), (col + 1).min(cols - 1));
+
+        let dfdx = (self.vals[row_next * cols + col] - self.vals[row_prev * cols + col])
+            / ((row_next - row_prev) as f32 * self.domain.dx());
+        let dfdy = (self.vals[row * cols + col_next] - self.vals[row * cols + col_prev])
+            / ((col_next - col_prev) as f32 * self.domain.dy());
+
+        (dfdx, dfdy)
+    }
+
+    // Interpolates the lattice function `node` at (x, y) with the configured scheme
+    fn sample(&self, x: f32, y: f32, node: impl Fn(usize, usize) -> f32) -> f32 {
+        let domain = self.domain;
+        if !domain.contains(x, y) {
+            return 0.0;
+        }
+
+        let (last_row, last_col) = (self.rows - 1, self.cols - 1);
+        let row_f = (x / (0.5 * domain.width) + 1.0) * domain.nx as f32;
+        let col_f = (y / (0.5 * domain.height) + 1.0) * domain.ny as f32;
+
+        if self.interpolation == Interpolation::Nearest {
+            return node(row_f.round() as usize, col_f.round() as usize);
+        }
+
+        // Lower corner of the cell containing the point, and the position inside of it
+        let row = (row_f.floor() as usize).min(last_row - 1);
+        let col = (col_f.floor() as usize).min(last_col - 1);
+        let tx = row_f - row as f32;
+        let ty = col_f - col as f32;
+
+        match self.interpolation {
+            Interpolation::Bicubic => {
+                let clamp = |i: isize, last: usize| i.max(0).min(last as isize) as usize;
+                let mut rows = [0.0; 4];
+
+                for (k, r) in rows.iter_mut().enumerate() {
+                    let r_idx = clamp(row as isize + k as isize - 1, last_row);
+                    *r = catmull_rom(
+                        node(r_idx, clamp(col as isize - 1, last_col)),
+                        node(r_idx, col),
+                        node(r_idx, col + 1),
+                        node(r_idx, clamp(col as isize + 2, last_col)),
+                        ty,
+                    );
+                }
+
+                catmull_rom(rows[0], rows[1], rows[2], rows[3], tx)
+            },
+            _ => {
+                let near = lerp(node(row, col), node(row, col + 1), ty);
+                let far = lerp(node(row + 1, col), node(row + 1, col + 1), ty);
+
+                lerp(near, far, tx)
+            },
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Cubic through p1 (t = 0) and p2 (t = 1) with tangents taken from p0 and p3
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
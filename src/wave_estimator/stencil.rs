@@ -0,0 +1,151 @@
+use std::ops::Range;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::boundary::{Boundaries, BoundaryCondition};
+
+// Five point laplacian over the row-major lattice, which knows which edge cells get integrated
+// and where their ghost neighbours live
+#[derive(Copy, Clone)]
+pub(super) struct Stencil {
+    pub rows: usize,
+    pub cols: usize,
+    pub boundaries: Boundaries,
+    pub inv_dx2: f32,
+    pub inv_dy2: f32,
+}
+
+impl Stencil {
+    // Calls `f` with the index and laplacian of `u` at every cell that is integrated, i.e. the
+    // interior plus the cells of free and periodic edges
+    #[inline]
+    pub fn for_each(&self, u: &[f32], mut f: impl FnMut(usize, f32)) {
+        self.for_each_in_rows(u, 1..(self.rows - 1), &mut f);
+        self.for_each_edge(u, f);
+    }
+
+    // Interior cells of the given rows, which must not include the first or last one
+    #[inline]
+    pub fn for_each_in_rows(&self, u: &[f32], rows: Range<usize>, mut f: impl FnMut(usize, f32)) {
+        let cols = self.cols;
+        let (inv_dx2, inv_dy2) = (self.inv_dx2, self.inv_dy2);
+
+        for row in rows {
+            let base = row * cols;
+            for col in 1..(cols - 1) {
+                let i = base + col;
+                let center = u[i];
+
+                let d2fdx2 = (u[i + cols] - 2.0 * center + u[i - cols]) * inv_dx2;
+                let d2fdy2 = (u[i + 1] - 2.0 * center + u[i - 1]) * inv_dy2;
+
+                f(i, d2fdx2 + d2fdy2);
+            }
+        }
+    }
+
+    // Integrated cells on the edges of the lattice
+    pub fn for_each_edge(&self, u: &[f32], mut f: impl FnMut(usize, f32)) {
+        let (rows, cols) = (self.rows, self.cols);
+        let (inv_dx2, inv_dy2) = (self.inv_dx2, self.inv_dy2);
+
+        // Free and periodic edges read their missing neighbour from a mirrored or wrapped-around
+        // ghost cell
+        let b = self.boundaries;
+        let (last_row, last_col) = (rows - 1, cols - 1);
+        let edge_cells = (0..cols).map(|col| (0, col))
+            .chain((0..cols).map(|col| (last_row, col)))
+            .chain((1..last_row).map(|row| (row, 0)))
+            .chain((1..last_row).map(|row| (row, last_col)));
+
+        for (row, col) in edge_cells {
+            if !self.is_dynamic_cell(row, col) {
+                continue;
+            }
+
+            let i = row * cols + col;
+            let center = u[i];
+
+            let x_next = Stencil::neighbour(row, 1, rows, b.x_min, b.x_max);
+            let x_prev = Stencil::neighbour(row, -1, rows, b.x_min, b.x_max);
+            let y_next = Stencil::neighbour(col, 1, cols, b.y_min, b.y_max);
+            let y_prev = Stencil::neighbour(col, -1, cols, b.y_min, b.y_max);
+
+            let d2fdx2 = (u[x_next * cols + col] - 2.0 * center + u[x_prev * cols + col]) * inv_dx2;
+            let d2fdy2 = (u[row * cols + y_next] - 2.0 * center + u[row * cols + y_prev]) * inv_dy2;
+
+            f(i, d2fdx2 + d2fdy2);
+        }
+    }
+
+    // Calls `kernel(i, j, laplacian, band)` for every interior cell, where `bands` yields the
+    // outputs of each band of `band_len()` cells and `j` is the index of the cell within its
+    // band. Bands run in parallel, and each one visits its cells in the same order and with the
+    // same arithmetic as `for_each`.
+    #[cfg(feature = "parallel")]
+    pub fn par_for_each_in_bands<T: Send>(
+        &self,
+        u: &[f32],
+        bands: impl IndexedParallelIterator<Item = T>,
+        kernel: impl Fn(usize, usize, f32, &mut T) + Sync,
+    ) {
+        let rows_per_band = self.rows_per_band();
+        let band_len = rows_per_band * self.cols;
+
+        bands.enumerate().for_each(|(band, mut outputs)| {
+            let offset = band * band_len;
+
+            self.for_each_in_rows(u, self.band_rows(band, rows_per_band), |i, laplacian| {
+                kernel(i, i - offset, laplacian, &mut outputs);
+            });
+        });
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn band_len(&self) -> usize {
+        self.rows_per_band() * self.cols
+    }
+
+    // Number of whole rows in each band the lattice is split into for parallel updates
+    #[cfg(feature = "parallel")]
+    fn rows_per_band(&self) -> usize {
+        // A few bands per thread keeps the work balanced when some threads are busy elsewhere
+        let bands = 4 * rayon::current_num_threads();
+        ((self.rows + bands - 1) / bands).max(1)
+    }
+
+    // Interior rows covered by the `band`-th band of `rows_per_band` rows
+    #[cfg(feature = "parallel")]
+    fn band_rows(&self, band: usize, rows_per_band: usize) -> Range<usize> {
+        let start = (band * rows_per_band).max(1);
+        let end = ((band + 1) * rows_per_band).min(self.rows - 1);
+
+        start..end.max(start)
+    }
+
+    pub fn is_dynamic_cell(&self, row: usize, col: usize) -> bool {
+        let b = &self.boundaries;
+
+        (row != 0 || b.x_min.is_dynamic()) &&
+            (row != self.rows - 1 || b.x_max.is_dynamic()) &&
+            (col != 0 || b.y_min.is_dynamic()) &&
+            (col != self.cols - 1 || b.y_max.is_dynamic())
+    }
+
+    // Index of the neighbour `delta` steps away from `i` along an axis of `size` points, falling
+    // back to a ghost cell when it lies outside the lattice
+    fn neighbour(
+        i: usize, delta: isize, size: usize, lo: BoundaryCondition, hi: BoundaryCondition
+    ) -> usize {
+        let j = i as isize + delta;
+
+        if j < 0 {
+            if lo == BoundaryCondition::Periodic { size - 2 } else { 1 }
+        } else if j >= size as isize {
+            if hi == BoundaryCondition::Periodic { 1 } else { size - 2 }
+        } else {
+            j as usize
+        }
+    }
+}